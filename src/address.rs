@@ -0,0 +1,53 @@
+use std::{fmt, str::FromStr};
+use ed25519_dalek::VerifyingKey;
+use sha2::{Digest, Sha256};
+use anyhow::{Result, bail};
+
+const PREFIX: &str = "oct";
+const DIGEST_LEN: usize = 32;
+
+/// An octra account address: `oct` followed by the base58 encoding of the
+/// sha256 digest of the account's ed25519 public key.
+///
+/// The format has no separate checksum, so validation is limited to the
+/// prefix, the base58 alphabet and the decoded digest length.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Address([u8; DIGEST_LEN]);
+
+impl Address {
+    pub fn from_public_key(key: &VerifyingKey) -> Self {
+        Address(Sha256::digest(key.as_bytes()).into())
+    }
+
+    pub fn parse(s: &str) -> Result<Self> {
+        let body = match s.strip_prefix(PREFIX) {
+            Some(body) if !body.is_empty() => body,
+            _ => bail!("'{}' is not an octra address (expected {}...)", s, PREFIX),
+        };
+        let bytes = match bs58::decode(body).into_vec() {
+            Ok(bytes) => bytes,
+            Err(e) => bail!("'{}' is not an octra address ({})", s, e),
+        };
+        match bytes.try_into() {
+            Ok(digest) => Ok(Address(digest)),
+            Err(bytes) => bail!(
+                "'{}' is not an octra address (decodes to {} bytes, expected {})",
+                s, bytes.len(), DIGEST_LEN
+            ),
+        }
+    }
+}
+
+impl FromStr for Address {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Address::parse(s)
+    }
+}
+
+impl fmt::Display for Address {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", PREFIX, bs58::encode(self.0).into_string())
+    }
+}
@@ -0,0 +1,98 @@
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de};
+use std::{fmt, str::FromStr};
+use anyhow::{Result, bail};
+
+/// Decimal places of an oct amount.
+const DECIMALS: usize = 6;
+
+/// An exact amount of oct, counted in micro oct.
+///
+/// Parsing and formatting go through the decimal oct representation with up
+/// to 6 places, so no amount is ever rounded on its way through a float:
+///
+/// ```
+/// use ocs01_test::Amount;
+///
+/// let amount: Amount = "12.5".parse().unwrap();
+/// assert_eq!(amount.micro(), 12_500_000);
+/// assert_eq!(amount.to_string(), "12.500000");
+/// assert_eq!(Amount::from_micro(1).to_string(), "0.000001");
+///
+/// // beyond what an f64 holds exactly
+/// let large: Amount = "123456789012345.678901".parse().unwrap();
+/// assert_eq!(large.micro(), 123_456_789_012_345_678_901);
+///
+/// assert!("0.0000001".parse::<Amount>().is_err());
+/// assert!("-1".parse::<Amount>().is_err());
+/// ```
+///
+/// In request bodies an amount is a decimal string of micro oct, as the
+/// node expects it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Amount(u128);
+
+impl Amount {
+    pub const ZERO: Amount = Amount(0);
+    /// One oct.
+    pub const OCT: Amount = Amount(1_000_000);
+
+    pub const fn from_micro(micro: u128) -> Self {
+        Amount(micro)
+    }
+
+    pub const fn micro(self) -> u128 {
+        self.0
+    }
+
+    pub fn checked_add(self, other: Amount) -> Option<Amount> {
+        self.0.checked_add(other.0).map(Amount)
+    }
+
+    pub fn checked_sub(self, other: Amount) -> Option<Amount> {
+        self.0.checked_sub(other.0).map(Amount)
+    }
+}
+
+impl FromStr for Amount {
+    type Err = anyhow::Error;
+
+    /// Parses oct like `12`, `12.5` or `.000001`.
+    fn from_str(s: &str) -> Result<Self> {
+        let (whole, fraction) = s.split_once('.').unwrap_or((s, ""));
+        let digits = |part: &str| part.bytes().all(|b| b.is_ascii_digit());
+        if whole.is_empty() && fraction.is_empty() || !digits(whole) || !digits(fraction) {
+            bail!("'{}' is not an amount of oct", s);
+        }
+        if fraction.len() > DECIMALS {
+            bail!("'{}' has more than {} decimal places", s, DECIMALS);
+        }
+        let parse = |part: &str| if part.is_empty() { Some(0) } else { part.parse::<u128>().ok() };
+        let micro = parse(whole)
+            .and_then(|whole| whole.checked_mul(Amount::OCT.0))
+            .zip(parse(&format!("{:0<width$}", fraction, width = DECIMALS)))
+            .and_then(|(whole, fraction)| whole.checked_add(fraction));
+        match micro {
+            Some(micro) => Ok(Amount(micro)),
+            None => bail!("'{}' is too large", s),
+        }
+    }
+}
+
+impl fmt::Display for Amount {
+    /// Oct with all 6 decimal places.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{:0width$}", self.0 / Amount::OCT.0, self.0 % Amount::OCT.0, width = DECIMALS)
+    }
+}
+
+impl Serialize for Amount {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for Amount {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?.parse().map(Amount).map_err(de::Error::custom)
+    }
+}
@@ -0,0 +1,93 @@
+use serde::Deserialize;
+use serde_json::Value;
+use std::{fs, path::Path};
+use anyhow::{Context, Result, bail};
+
+use crate::expect::Expect;
+
+/// One step of a batch script. Exactly one of `view` or `call` names the
+/// method to run.
+///
+/// ```yaml
+/// - view: factorial
+///   params: [5]
+///   expect: 120
+/// - view: vectorMagnitude
+///   params: [1, 1]
+///   expect: { approx: 1.4142, tolerance: 0.0001 }
+/// - name: claim
+///   call: claimToken
+///   wait: true
+/// ```
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Step {
+    pub name: Option<String>,
+    pub view: Option<String>,
+    pub call: Option<String>,
+    #[serde(default)]
+    pub params: Vec<Value>,
+    /// Expectation the view result must meet.
+    pub expect: Option<Expect>,
+    /// Wait for a call to be confirmed; the step fails on timeout.
+    #[serde(default)]
+    pub wait: bool,
+    /// Confirmation timeout in seconds.
+    pub timeout: Option<u64>,
+}
+
+pub enum StepKind<'a> {
+    View(&'a str),
+    Call(&'a str),
+}
+
+impl Step {
+    pub fn kind(&self) -> StepKind<'_> {
+        match (&self.view, &self.call) {
+            (Some(method), None) => StepKind::View(method),
+            (None, Some(method)) => StepKind::Call(method),
+            _ => unreachable!("checked in load"),
+        }
+    }
+
+    pub fn method(&self) -> &str {
+        match self.kind() {
+            StepKind::View(method) | StepKind::Call(method) => method,
+        }
+    }
+
+    pub fn label(&self) -> &str {
+        self.name.as_deref().unwrap_or_else(|| self.method())
+    }
+
+    /// Params as the raw strings a user would type, so they go through the
+    /// same interface validation as command line params.
+    pub fn raw_params(&self) -> Vec<String> {
+        self.params.iter().map(|p| match p {
+            Value::String(s) => s.clone(),
+            other => other.to_string(),
+        }).collect()
+    }
+}
+
+/// Reads a batch script. `.json` files are parsed as json, anything else as
+/// yaml.
+pub fn load(path: &Path) -> Result<Vec<Step>> {
+    let data = fs::read_to_string(path)
+        .with_context(|| format!("cannot read {}", path.display()))?;
+    let steps: Vec<Step> = if path.extension().is_some_and(|ext| ext == "json") {
+        serde_json::from_str(&data).with_context(|| format!("cannot parse {}", path.display()))?
+    } else {
+        serde_yaml::from_str(&data).with_context(|| format!("cannot parse {}", path.display()))?
+    };
+
+    for (i, step) in steps.iter().enumerate() {
+        if step.view.is_some() == step.call.is_some() {
+            bail!("step {} must have exactly one of view or call", i + 1);
+        }
+        if step.call.is_some() && step.expect.is_some() {
+            bail!("step {}: expect only applies to view steps", i + 1);
+        }
+    }
+    Ok(steps)
+}
@@ -0,0 +1,210 @@
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::time::{Duration, Instant};
+use base64::{Engine as _, engine::general_purpose};
+use ed25519_dalek::SigningKey;
+use anyhow::{Result, anyhow};
+
+use crate::{
+    address::Address,
+    amount::Amount,
+    error::Error,
+    nonce::NonceManager,
+    rpc::{self, Client, RetryPolicy, RpcFailure, api_call},
+    tx::{ContractCall, Transaction},
+};
+
+/// Time between `/tx` polls while waiting for a confirmation.
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Deserialize)]
+struct BalanceResponse {
+    balance_raw: String,
+    nonce: u64,
+}
+
+/// Typed access to the node endpoints the ocs01 contract needs.
+///
+/// ```no_run
+/// # async fn demo() -> anyhow::Result<()> {
+/// use ocs01_test::{OctraClient, Wallet};
+///
+/// let wallet = Wallet::load("wallet.json".as_ref())?;
+/// let client = OctraClient::new(&wallet.endpoints())?;
+/// let (balance, nonce) = client.balance(&wallet.addr).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct OctraClient {
+    rpc: Client,
+}
+
+impl OctraClient {
+    /// A client for `endpoints`, in priority order, with a 100s request
+    /// timeout and 3 retries.
+    pub fn new(endpoints: &[String]) -> Result<Self> {
+        let retry = RetryPolicy {
+            retries: 3,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+        };
+        OctraClient::with_policy(endpoints, Duration::from_secs(100), retry)
+    }
+
+    pub fn with_policy(endpoints: &[String], timeout: Duration, retry: RetryPolicy) -> Result<Self> {
+        let rpc = Client::new(timeout, retry).map_err(Error::Rpc)?;
+        rpc.use_endpoints(endpoints);
+        Ok(OctraClient { rpc })
+    }
+
+    /// Switches to other endpoints, e.g. after changing wallets.
+    pub fn use_endpoints(&self, endpoints: &[String]) {
+        self.rpc.use_endpoints(endpoints);
+    }
+
+    /// Balance and the nonce of the last confirmed transaction.
+    pub async fn balance(&self, addr: &str) -> Result<(Amount, u64)> {
+        let balance: BalanceResponse = api_call(&self.rpc, "GET", &format!("/balance/{}", addr), None, true).await?;
+        let balance_raw = balance.balance_raw.parse::<u128>()
+            .map_err(|e| Error::Rpc(anyhow!("bad balance_raw {:?}: {}", balance.balance_raw, e)))?;
+        Ok((Amount::from_micro(balance_raw), balance.nonce))
+    }
+
+    /// Highest nonce of `addr` among the transactions the node has staged
+    /// but not yet confirmed. The endpoint is optional, so failures just
+    /// yield `None`.
+    pub async fn staged_nonce(&self, addr: &str) -> Option<u64> {
+        self.staged_nonces(addr).await?.into_iter().max()
+    }
+
+    /// Nonces of `addr` in the node's staging area, `None` if it could not be
+    /// read.
+    async fn staged_nonces(&self, addr: &str) -> Option<Vec<u64>> {
+        let staging: Value = api_call(&self.rpc, "GET", "/staging", None, true).await.ok()?;
+        Some(staging["staged_transactions"].as_array()?
+            .iter()
+            .filter(|tx| tx["from"] == addr)
+            .filter_map(|tx| tx["nonce"].as_u64().or_else(|| tx["nonce"].as_str()?.parse().ok()))
+            .collect())
+    }
+
+    /// Reserves the nonce for the next transaction of `addr`, taking the
+    /// confirmed nonce, the node's staging area and nonces `nonces` already
+    /// handed out into account.
+    pub async fn next_nonce(&self, nonces: &mut NonceManager, addr: &str) -> Result<u64> {
+        let (_, confirmed) = self.balance(addr).await?;
+        let staged = self.staged_nonces(addr).await
+            .map(|staged| staged.into_iter().fold(confirmed, u64::max));
+        Ok(nonces.next(addr, confirmed, staged))
+    }
+
+    /// Runs a view method. `None` means the call succeeded without a result;
+    /// a call the node refused fails with [`RpcFailure::Rejected`].
+    pub async fn view(&self, contract: &str, method: &str, params: &[Value], caller: &str) -> Result<Option<Value>> {
+        let response: Value = api_call(
+            &self.rpc,
+            "POST",
+            "/contract/call-view",
+            Some(json!({
+                "contract": contract,
+                "method": method,
+                "params": params,
+                "caller": caller
+            })),
+            true
+        ).await?;
+
+        if response["status"] != "success" {
+            let reason = match &response["error"] {
+                Value::String(error) => error.clone(),
+                Value::Null => format!("status {}", response["status"]),
+                error => error.to_string(),
+            };
+            return Err(Error::Rpc(RpcFailure::Rejected(reason).into()).into());
+        }
+        Ok(Some(response["result"].clone()).filter(|result| !result.is_null()))
+    }
+
+    /// Signs and submits a call of `method` with `nonce` and returns the tx
+    /// hash.
+    pub async fn call(
+        &self,
+        sk: &SigningKey,
+        contract: &str,
+        method: &str,
+        params: &[Value],
+        nonce: u64,
+    ) -> Result<String> {
+        let caller = Address::from_public_key(&sk.verifying_key()).to_string();
+        let mut call = ContractCall::new(&caller, contract, method, params, nonce).map_err(Error::Signing)?;
+        call.sign(sk).map_err(Error::Signing)?;
+        self.submit(&call).await
+    }
+
+    /// Signs and submits a plain transfer of `amount` to `to` and returns the
+    /// tx hash.
+    pub async fn transfer(&self, sk: &SigningKey, to: &str, amount: Amount, ou: u64, nonce: u64) -> Result<String> {
+        let from = Address::from_public_key(&sk.verifying_key()).to_string();
+        let tx = Transaction::now(&from, to, amount, nonce, ou).map_err(Error::Signing)?;
+        let response: Value = api_call(
+            &self.rpc,
+            "POST",
+            "/send-tx",
+            Some(json!({
+                "from": tx.from,
+                "to_": tx.to,
+                "amount": tx.amount,
+                "nonce": tx.nonce,
+                "ou": tx.ou.to_string(),
+                "timestamp": tx.timestamp,
+                "signature": tx.sign(sk),
+                "public_key": general_purpose::STANDARD.encode(sk.verifying_key().to_bytes())
+            })),
+            false
+        ).await?;
+
+        Ok(response["tx_hash"].as_str().unwrap_or("").to_string())
+    }
+
+    /// Submits a call signed elsewhere and returns the tx hash.
+    pub async fn submit(&self, call: &ContractCall) -> Result<String> {
+        if !call.is_signed() {
+            return Err(Error::Signing(anyhow!("the call is not signed")).into());
+        }
+        let response: Value = api_call(
+            &self.rpc,
+            "POST",
+            "/call-contract",
+            Some(serde_json::to_value(call)?),
+            // a resent tx would at best be rejected for its nonce
+            false
+        ).await?;
+
+        Ok(response["tx_hash"].as_str().unwrap_or("").to_string())
+    }
+
+    /// Polls until `tx_hash` is confirmed (`true`) or `timeout` passes
+    /// (`false`). `on_pending` runs after every poll that found it pending.
+    pub async fn wait(&self, tx_hash: &str, timeout: Duration, mut on_pending: impl FnMut()) -> Result<bool> {
+        let start = Instant::now();
+
+        loop {
+            if start.elapsed() > timeout {
+                return Ok(false);
+            }
+
+            let tx: Result<Value> = api_call(&self.rpc, "GET", &format!("/tx/{}", tx_hash), None, true).await;
+            match tx {
+                Ok(tx) if tx["status"] == "confirmed" => return Ok(true),
+                Ok(_) => {}
+                // a flaky node should not end the wait before the timeout does
+                Err(e) if rpc::failure(&e).is_some_and(RpcFailure::is_transient) => {}
+                Err(e) => return Err(e),
+            }
+
+            on_pending();
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    }
+}
@@ -0,0 +1,49 @@
+use std::path::{Path, PathBuf};
+
+pub const WALLET_FILE: &str = "wallet.json";
+pub const INTERFACE_FILE: &str = "exec_interface.json";
+pub const PROFILES_FILE: &str = "profiles.json";
+
+/// Name of the directory under the user config dir (`$XDG_CONFIG_HOME` or
+/// its platform equivalent) that is searched for the files above.
+const CONFIG_DIR: &str = "ocs01-test";
+
+/// File locations and overrides for one run.
+pub struct Config {
+    pub wallet: PathBuf,
+    pub interface: PathBuf,
+    pub profiles: PathBuf,
+    /// Replaces the rpc endpoints stored in the wallet or profile.
+    pub rpc: Option<Vec<String>>,
+}
+
+impl Config {
+    pub fn new(
+        wallet: Option<PathBuf>,
+        interface: Option<PathBuf>,
+        profiles: Option<PathBuf>,
+        rpc: Option<Vec<String>>,
+    ) -> Self {
+        Config {
+            wallet: wallet.unwrap_or_else(|| locate(WALLET_FILE)),
+            interface: interface.unwrap_or_else(|| locate(INTERFACE_FILE)),
+            profiles: profiles.unwrap_or_else(|| locate(PROFILES_FILE)),
+            rpc,
+        }
+    }
+}
+
+/// Resolves a file that was not given explicitly: the current directory wins
+/// if the file exists there, then the config dir. When neither has it the
+/// current directory path is returned so errors and newly created files
+/// point where users expect.
+fn locate(name: &str) -> PathBuf {
+    let local = Path::new(name);
+    if local.exists() {
+        return local.to_path_buf();
+    }
+    match dirs::config_dir().map(|dir| dir.join(CONFIG_DIR).join(name)) {
+        Some(path) if path.exists() => path,
+        _ => local.to_path_buf(),
+    }
+}
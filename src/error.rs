@@ -0,0 +1,69 @@
+use thiserror::Error;
+
+use crate::rpc::RpcFailure;
+
+/// Failure categories surfaced to the user. Each maps to its own process
+/// exit code so scripts can tell a bad wallet from an unreachable node.
+///
+/// | code | kind      |
+/// |------|-----------|
+/// | 1    | other, e.g. tx not confirmed in time |
+/// | 2    | input     |
+/// | 3    | wallet    |
+/// | 4    | interface |
+/// | 5    | rpc       |
+/// | 6    | signing   |
+/// | 7    | assertion, also a `run` whose failed steps were all assertions |
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("{0:#}")]
+    Input(anyhow::Error),
+    #[error("{0:#}")]
+    Wallet(anyhow::Error),
+    #[error("{0:#}")]
+    Interface(anyhow::Error),
+    #[error("{0:#}")]
+    Rpc(anyhow::Error),
+    #[error("{0:#}")]
+    Signing(anyhow::Error),
+    #[error("{0:#}")]
+    Assertion(anyhow::Error),
+}
+
+impl Error {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Error::Input(_) => "input",
+            Error::Wallet(_) => "wallet",
+            Error::Interface(_) => "interface",
+            Error::Rpc(_) => "rpc",
+            Error::Signing(_) => "signing",
+            Error::Assertion(_) => "assertion",
+        }
+    }
+
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            Error::Input(_) => 2,
+            Error::Wallet(_) => 3,
+            Error::Interface(_) => 4,
+            Error::Rpc(_) => 5,
+            Error::Signing(_) => 6,
+            Error::Assertion(_) => 7,
+        }
+    }
+
+    pub fn hint(&self) -> &'static str {
+        match self {
+            Error::Input(_) => "check the command arguments, see --help",
+            Error::Wallet(_) => "check the wallet file (--wallet / OCS01_WALLET), or create one with `wallet new` / `wallet import`",
+            Error::Interface(_) => "pass --interface / OCS01_INTERFACE, or copy EI/exec_interface.json to the working or config dir",
+            Error::Rpc(e) if matches!(e.downcast_ref(), Some(RpcFailure::Rejected(_))) => {
+                "the node refused the request, check the params against the contract"
+            }
+            Error::Rpc(_) => "check the rpc url (--rpc / OCS01_RPC, or the one in the wallet) and that the node is reachable",
+            Error::Signing(_) => "check the wallet key and the system clock",
+            Error::Assertion(_) => "the contract returned something other than expected",
+        }
+    }
+}
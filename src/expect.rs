@@ -0,0 +1,128 @@
+use serde::Deserialize;
+use serde_json::Value;
+use regex::Regex;
+use anyhow::{Result, anyhow, bail};
+
+const DEFAULT_TOLERANCE: f64 = 1e-9;
+
+/// Expectation on the raw result of a view call.
+///
+/// A plain value must equal the result exactly. A matcher object selects part
+/// of the result with `path` and applies every check it sets:
+///
+/// ```yaml
+/// expect: 120
+/// expect: { approx: 1.4142, tolerance: 0.0001 }
+/// expect: { regex: "^oct" }
+/// expect: { path: "$.items[0].name", equals: "foo" }
+/// ```
+///
+/// To expect an object that looks like a matcher, wrap it in `equals`.
+#[derive(Deserialize, Debug)]
+#[serde(untagged)]
+pub enum Expect {
+    Matcher(Matcher),
+    Exact(Value),
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields)]
+pub struct Matcher {
+    /// Location inside the result, e.g. `$.a.b[0]`. Defaults to the root.
+    pub path: Option<String>,
+    pub equals: Option<Value>,
+    /// Number the result must be within `tolerance` of. Numeric strings are
+    /// accepted as results.
+    pub approx: Option<f64>,
+    pub tolerance: Option<f64>,
+    /// Pattern matched against the result, strings as-is and anything else
+    /// in its json form.
+    pub regex: Option<String>,
+}
+
+impl Expect {
+    /// Returns an error describing the first mismatch.
+    pub fn check(&self, result: &Value) -> Result<()> {
+        match self {
+            Expect::Exact(expected) => check_equals(expected, result, "$"),
+            Expect::Matcher(matcher) => matcher.check(result),
+        }
+    }
+}
+
+impl Matcher {
+    pub fn is_empty(&self) -> bool {
+        self.equals.is_none() && self.approx.is_none() && self.regex.is_none()
+    }
+
+    pub fn check(&self, result: &Value) -> Result<()> {
+        if self.is_empty() {
+            bail!("expectation has no equals, approx or regex");
+        }
+        let path = self.path.as_deref().unwrap_or("$");
+        let value = select(result, path)?;
+
+        if let Some(expected) = &self.equals {
+            check_equals(expected, value, path)?;
+        }
+        if let Some(expected) = self.approx {
+            let tolerance = self.tolerance.unwrap_or(DEFAULT_TOLERANCE);
+            let actual = as_number(value)
+                .ok_or_else(|| anyhow!("at {}: expected a number, got {}", path, value))?;
+            if (actual - expected).abs() > tolerance {
+                bail!("at {}: expected {} ± {}, got {}", path, expected, tolerance, actual);
+            }
+        }
+        if let Some(pattern) = &self.regex {
+            let re = Regex::new(pattern).map_err(|e| anyhow!("bad regex {}: {}", pattern, e))?;
+            let text = match value {
+                Value::String(s) => s.clone(),
+                other => other.to_string(),
+            };
+            if !re.is_match(&text) {
+                bail!("at {}: {} does not match {}", path, value, pattern);
+            }
+        }
+        Ok(())
+    }
+}
+
+fn check_equals(expected: &Value, actual: &Value, path: &str) -> Result<()> {
+    if expected != actual {
+        bail!("at {}: expected {}, got {}", path, expected, actual);
+    }
+    Ok(())
+}
+
+fn as_number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+/// Resolves a simple json path: `$` followed by `.key` and `[index]`
+/// segments. The leading `$` and `.` are optional.
+fn select<'a>(root: &'a Value, path: &str) -> Result<&'a Value> {
+    let mut rest = path.strip_prefix('$').unwrap_or(path);
+    let mut value = root;
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix('[') {
+            let end = after.find(']').ok_or_else(|| anyhow!("bad path {}: unclosed [", path))?;
+            let index: usize = after[..end].trim().parse()
+                .map_err(|_| anyhow!("bad path {}: index {} is not a number", path, &after[..end]))?;
+            value = value.get(index)
+                .ok_or_else(|| anyhow!("at {}: no element {} in {}", path, index, value))?;
+            rest = &after[end + 1..];
+        } else {
+            let segment = rest.strip_prefix('.').unwrap_or(rest);
+            let end = segment.find(['.', '[']).unwrap_or(segment.len());
+            let key = &segment[..end];
+            value = value.get(key)
+                .ok_or_else(|| anyhow!("at {}: no field {} in {}", path, key, value))?;
+            rest = &segment[end..];
+        }
+    }
+    Ok(value)
+}
@@ -0,0 +1,284 @@
+use serde_json::Value;
+use std::fmt;
+use rand_core::{OsRng, RngCore};
+
+use crate::params::{Param, ParamType};
+
+/// Range used for number params that declare no bounds.
+const DEFAULT_RANGE: (i64, i64) = (-1000, 1000);
+/// Relative tolerance when comparing non-integer results.
+const REAL_TOLERANCE: f64 = 1e-9;
+
+/// What the reference implementation says a method should return.
+#[derive(Debug, PartialEq)]
+pub enum Expected {
+    Int(i128),
+    Real(f64),
+    Bool(bool),
+    /// The exact result does not fit in an i128, so there is nothing to
+    /// compare against.
+    Overflow,
+    /// The inputs have no mathematical result (division by zero and the
+    /// like); the contract is expected to reject them.
+    Undefined,
+}
+
+impl fmt::Display for Expected {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expected::Int(n) => write!(f, "{}", n),
+            Expected::Real(x) => write!(f, "{}", x),
+            Expected::Bool(b) => write!(f, "{}", b),
+            Expected::Overflow => write!(f, "overflow"),
+            Expected::Undefined => write!(f, "undefined"),
+        }
+    }
+}
+
+/// What the node answered to a view call.
+pub enum Answer<'a> {
+    /// A successful call, `None` if it returned no result.
+    Result(Option<&'a Value>),
+    /// The contract refused the inputs.
+    Rejected(&'a str),
+    /// The call did not get an answer from the contract at all.
+    Failed(&'a str),
+}
+
+impl fmt::Display for Answer<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Answer::Result(Some(value)) => write!(f, "got {}", value),
+            Answer::Result(None) => write!(f, "got no result"),
+            Answer::Rejected(reason) => write!(f, "rejected ({})", reason),
+            Answer::Failed(error) => write!(f, "call failed ({})", error),
+        }
+    }
+}
+
+pub enum Verdict {
+    Match,
+    /// No comparison possible because the reference overflowed; says what
+    /// the contract did instead.
+    Skipped(String),
+    Diverged(String),
+}
+
+/// Methods of the exec interface that have a local reference implementation.
+pub const METHODS: &[&str] = &[
+    "dotProduct",
+    "vectorMagnitude",
+    "power",
+    "factorial",
+    "fibonacci",
+    "gcd",
+    "isPrime",
+    "matrixDeterminant2x2",
+    "linearInterpolate",
+    "modularExponentiation",
+];
+
+/// Computes the expected result of `method` for integer `args`, or `None`
+/// if there is no reference for it.
+pub fn reference(method: &str, args: &[i64]) -> Option<Expected> {
+    let a: Vec<i128> = args.iter().map(|&n| n as i128).collect();
+    Some(match (method, a.as_slice()) {
+        ("dotProduct", &[x1, y1, x2, y2]) => int(x1.checked_mul(x2).zip(y1.checked_mul(y2)).and_then(|(p, q)| p.checked_add(q))),
+        ("vectorMagnitude", &[x, y]) => Expected::Real(((x * x + y * y) as f64).sqrt()),
+        ("power", &[_, exp]) if exp < 0 => Expected::Undefined,
+        ("power", &[base, exp]) => int(u32::try_from(exp).ok().and_then(|e| base.checked_pow(e))),
+        ("factorial", &[n]) if n < 0 => Expected::Undefined,
+        ("factorial", &[n]) => int((1..=n).try_fold(1i128, |acc, k| acc.checked_mul(k))),
+        ("fibonacci", &[n]) if n < 0 => Expected::Undefined,
+        ("fibonacci", &[n]) => int(fibonacci(n)),
+        ("gcd", &[a, b]) => Expected::Int(gcd(a.abs(), b.abs())),
+        ("isPrime", &[n]) => Expected::Bool(is_prime(n)),
+        ("matrixDeterminant2x2", &[a, b, c, d]) => Expected::Int(a * d - b * c),
+        ("linearInterpolate", &[x0, _, x1, _, _]) if x0 == x1 => Expected::Undefined,
+        ("linearInterpolate", &[x0, y0, x1, y1, x]) => {
+            let (x0, y0, x1, y1, x) = (x0 as f64, y0 as f64, x1 as f64, y1 as f64, x as f64);
+            Expected::Real(y0 + (x - x0) * (y1 - y0) / (x1 - x0))
+        }
+        ("modularExponentiation", &[_, exp, m]) if m <= 0 || exp < 0 => Expected::Undefined,
+        ("modularExponentiation", &[base, exp, m]) => Expected::Int(mod_pow(base, exp, m)),
+        _ => return None,
+    })
+}
+
+fn int(n: Option<i128>) -> Expected {
+    n.map_or(Expected::Overflow, Expected::Int)
+}
+
+fn fibonacci(n: i128) -> Option<i128> {
+    let (mut a, mut b) = (0i128, 1i128);
+    for _ in 0..n {
+        (a, b) = (b, a.checked_add(b)?);
+    }
+    Some(a)
+}
+
+fn gcd(mut a: i128, mut b: i128) -> i128 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+fn is_prime(n: i128) -> bool {
+    if n < 2 {
+        return false;
+    }
+    let mut d = 2;
+    while d * d <= n {
+        if n % d == 0 {
+            return false;
+        }
+        d += 1;
+    }
+    true
+}
+
+fn mod_pow(base: i128, mut exp: i128, m: i128) -> i128 {
+    let mut base = base.rem_euclid(m);
+    let mut result = 1 % m;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = result * base % m;
+        }
+        base = base * base % m;
+        exp >>= 1;
+    }
+    result
+}
+
+/// Compares what the contract answered with the reference. A call that
+/// failed never matches.
+pub fn compare(expected: &Expected, answer: &Answer) -> Verdict {
+    let actual = match (expected, answer) {
+        (_, Answer::Failed(_)) => return Verdict::Diverged(format!("expected {}, {}", expected, answer)),
+        (Expected::Overflow, _) => return Verdict::Skipped(format!("reference overflows, contract {}", answer)),
+        (Expected::Undefined, Answer::Rejected(_) | Answer::Result(None)) => return Verdict::Match,
+        (_, Answer::Rejected(_) | Answer::Result(None)) => {
+            return Verdict::Diverged(format!("expected {}, {}", expected, answer));
+        }
+        (_, Answer::Result(Some(actual))) => *actual,
+    };
+    let matches = match expected {
+        Expected::Int(n) => match actual_int(actual) {
+            Some(a) => a == *n,
+            // results beyond 2^53 may arrive as lossy floats
+            None => actual_real(actual).is_some_and(|a| close(a, *n as f64)),
+        },
+        Expected::Real(x) => actual_real(actual).is_some_and(|a| close(a, *x)),
+        Expected::Bool(b) => actual_bool(actual) == Some(*b),
+        Expected::Overflow | Expected::Undefined => false,
+    };
+    if matches {
+        Verdict::Match
+    } else {
+        Verdict::Diverged(format!("expected {}, got {}", expected, actual))
+    }
+}
+
+fn actual_int(value: &Value) -> Option<i128> {
+    match value {
+        Value::Number(n) => n.as_i64().map(i128::from).or_else(|| n.as_u64().map(i128::from)),
+        Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+fn actual_real(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+fn actual_bool(value: &Value) -> Option<bool> {
+    match value {
+        Value::Bool(b) => Some(*b),
+        Value::String(s) if s == "true" => Some(true),
+        Value::String(s) if s == "false" => Some(false),
+        Value::Number(n) => n.as_u64().filter(|&n| n <= 1).map(|n| n == 1),
+        _ => None,
+    }
+}
+
+fn close(a: f64, b: f64) -> bool {
+    (a - b).abs() <= REAL_TOLERANCE * b.abs().max(1.0)
+}
+
+/// Small deterministic generator (splitmix64) so a failing run can be
+/// replayed with `--seed`.
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: Option<u64>) -> (Self, u64) {
+        let seed = seed.unwrap_or_else(|| OsRng.next_u64());
+        (Rng(seed), seed)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    fn range(&mut self, lo: i64, hi: i64) -> i64 {
+        let span = (hi as i128 - lo as i128 + 1) as u128;
+        (lo as i128 + (self.next() as u128 % span) as i128) as i64
+    }
+
+    /// Picks random inputs for `method` within the bounds of its params.
+    pub fn args(&mut self, method: &str, params: &[Param]) -> Option<Vec<i64>> {
+        let bounds: Vec<(i64, i64)> = params.iter().map(bounds).collect::<Option<_>>()?;
+        if let ("power", &[base, exp]) = (method, bounds.as_slice())
+            && !self.next().is_multiple_of(4)
+            && let Some(args) = self.power_near_limit(base, exp) {
+            return Some(args);
+        }
+        Some(bounds.into_iter().map(|(lo, hi)| self.range(lo, hi)).collect())
+    }
+
+    /// Uniform bases and exponents almost always overflow i128, which says
+    /// nothing about the contract. Instead pick an exponent and one of the two
+    /// bases whose powers sit just below and just above 2^63 or 2^64, where
+    /// 64-bit arithmetic in the contract overflows.
+    fn power_near_limit(&mut self, (base_lo, base_hi): (i64, i64), (exp_lo, exp_hi): (i64, i64)) -> Option<Vec<i64>> {
+        let bits = [63, 64][(self.next() % 2) as usize];
+        let limit = 1i128 << bits;
+        // above 39 the only roots left are 2 and 3, so try 2^63 or 2^64 itself
+        let exp = if self.next().is_multiple_of(8) { bits } else { self.range(2, 39) };
+        if !(exp_lo..=exp_hi).contains(&exp) {
+            return None;
+        }
+        // largest root whose power stays at or below the limit
+        let mut root = (limit as f64).powf(1.0 / exp as f64) as i64 + 1;
+        while (root as i128).checked_pow(exp as u32).is_none_or(|p| p > limit) {
+            root -= 1;
+        }
+        let base = (root + self.range(0, 1)) * if self.next().is_multiple_of(2) { 1 } else { -1 };
+        (base_lo..=base_hi).contains(&base).then(|| vec![base, exp])
+    }
+}
+
+/// Range to draw a param from. Params with only a `max` are treated as
+/// counts starting at zero.
+fn bounds(param: &Param) -> Option<(i64, i64)> {
+    if param.param_type != ParamType::Number {
+        return None;
+    }
+    let lo = param.min.map(|m| m.ceil() as i64);
+    let hi = param.max.map(|m| m.floor() as i64);
+    let (lo, hi) = match (lo, hi) {
+        (Some(lo), Some(hi)) => (lo, hi),
+        (Some(lo), None) => (lo, lo.saturating_add(DEFAULT_RANGE.1 - DEFAULT_RANGE.0)),
+        (None, Some(hi)) => (0.min(hi), hi),
+        (None, None) => DEFAULT_RANGE,
+    };
+    (lo <= hi).then_some((lo, hi))
+}
@@ -0,0 +1,65 @@
+use serde::Deserialize;
+use serde_json::Value;
+use std::{fs, path::Path};
+use anyhow::{Context, Result, anyhow};
+
+use crate::{address::Address, error::Error, params::Param};
+
+#[derive(Deserialize)]
+pub struct Method {
+    pub name: String,
+    pub label: String,
+    pub params: Vec<Param>,
+    /// `view` or `call`.
+    #[serde(rename = "type")]
+    pub method_type: String,
+}
+
+/// Contents of `exec_interface.json`: the contract address and its methods.
+#[derive(Deserialize)]
+pub struct Interface {
+    pub contract: String,
+    pub methods: Vec<Method>,
+}
+
+impl Interface {
+    pub fn load(path: &Path) -> Result<Self> {
+        let data = fs::read_to_string(path)
+            .with_context(|| format!("cannot read {}", path.display()))?;
+        let interface: Interface = serde_json::from_str(&data)
+            .with_context(|| format!("cannot parse {}", path.display()))?;
+        Address::parse(&interface.contract)
+            .with_context(|| format!("bad contract in {}", path.display()))?;
+        Ok(interface)
+    }
+
+    /// Looks up a method by name and checks that it is a `method_type` one.
+    pub fn method(&self, name: &str, method_type: &str) -> Result<&Method> {
+        let method = match self.methods.iter().find(|m| m.name == name) {
+            Some(m) => m,
+            None => return Err(Error::Input(anyhow!("unknown method: {}", name)).into()),
+        };
+        if method.method_type != method_type {
+            return Err(Error::Input(anyhow!("{} is a {} method", name, method.method_type)).into());
+        }
+        Ok(method)
+    }
+}
+
+impl Method {
+    /// Validates raw param strings against the interface and encodes them.
+    /// Trailing params that are optional or have a default may be left out.
+    pub fn encode_params(&self, params: &[String]) -> Result<Vec<Value>> {
+        let required = self.params.iter().rposition(|p| !p.can_omit()).map_or(0, |i| i + 1);
+        if params.len() < required || params.len() > self.params.len() {
+            let names: Vec<&str> = self.params.iter().map(|p| p.name.as_str()).collect();
+            return Err(Error::Input(anyhow!(
+                "{} expects {} params ({}), got {}", self.name, names.len(), names.join(", "), params.len()
+            )).into());
+        }
+        self.params.iter().enumerate().map(|(i, p)| {
+            let input = params.get(i).map_or("", String::as_str);
+            p.parse(input).map_err(|e| Error::Input(anyhow!("invalid {}: {}", p.name, e)).into())
+        }).collect()
+    }
+}
@@ -0,0 +1,91 @@
+use serde::{Deserialize, Serialize};
+use base64::{Engine as _, engine::general_purpose};
+use chacha20poly1305::{ChaCha20Poly1305, Key, KeyInit, Nonce, aead::Aead};
+use rand_core::{OsRng, RngCore};
+use anyhow::{Result, anyhow, bail};
+
+const KDF: &str = "scrypt";
+const CIPHER: &str = "chacha20poly1305";
+// scrypt cost for new keystores (n = 2^15, r = 8, p = 1)
+const LOG_N: u8 = 15;
+const R: u32 = 8;
+const P: u32 = 1;
+// upper bounds accepted from a keystore file, so a crafted one cannot make
+// decryption allocate or compute without bound (at most 128 * r * 2^log_n = 4 GiB)
+const MAX_LOG_N: u8 = 20;
+const MAX_R: u32 = 32;
+const MAX_P: u32 = 16;
+
+/// Passphrase protected private key as stored in `wallet.json`.
+///
+/// The encryption key is derived from the passphrase with scrypt and the
+/// 32 byte ed25519 seed is sealed with chacha20poly1305, so a wrong
+/// passphrase or a tampered file fails to decrypt instead of yielding a
+/// different key. Binary fields are base64.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Keystore {
+    pub kdf: String,
+    pub log_n: u8,
+    pub r: u32,
+    pub p: u32,
+    pub salt: String,
+    pub cipher: String,
+    pub nonce: String,
+    pub ciphertext: String,
+}
+
+impl Keystore {
+    pub fn encrypt(secret: &[u8; 32], passphrase: &str) -> Result<Self> {
+        let params = scrypt::Params::new(LOG_N, R, P, 32)
+            .map_err(|e| anyhow!("bad kdf params: {}", e))?;
+        let mut salt = [0u8; 16];
+        let mut nonce = [0u8; 12];
+        OsRng.fill_bytes(&mut salt);
+        OsRng.fill_bytes(&mut nonce);
+
+        let cipher = ChaCha20Poly1305::new(&derive_key(passphrase, &salt, &params)?);
+        let ciphertext = cipher.encrypt(Nonce::from_slice(&nonce), secret.as_slice())
+            .map_err(|_| anyhow!("encryption failed"))?;
+
+        Ok(Keystore {
+            kdf: KDF.to_string(),
+            log_n: params.log_n(),
+            r: params.r(),
+            p: params.p(),
+            salt: general_purpose::STANDARD.encode(salt),
+            cipher: CIPHER.to_string(),
+            nonce: general_purpose::STANDARD.encode(nonce),
+            ciphertext: general_purpose::STANDARD.encode(ciphertext),
+        })
+    }
+
+    pub fn decrypt(&self, passphrase: &str) -> Result<[u8; 32]> {
+        if self.kdf != KDF || self.cipher != CIPHER {
+            bail!("unsupported keystore ({} / {})", self.kdf, self.cipher);
+        }
+        if self.log_n > MAX_LOG_N || self.r > MAX_R || self.p > MAX_P {
+            bail!("keystore kdf params log_n={} r={} p={} exceed the limits log_n={} r={} p={}",
+                self.log_n, self.r, self.p, MAX_LOG_N, MAX_R, MAX_P);
+        }
+        let params = scrypt::Params::new(self.log_n, self.r, self.p, 32)
+            .map_err(|e| anyhow!("bad keystore kdf params: {}", e))?;
+        let salt = general_purpose::STANDARD.decode(&self.salt)?;
+        let nonce = general_purpose::STANDARD.decode(&self.nonce)?;
+        let ciphertext = general_purpose::STANDARD.decode(&self.ciphertext)?;
+        if nonce.len() != 12 {
+            bail!("bad keystore nonce length");
+        }
+
+        let cipher = ChaCha20Poly1305::new(&derive_key(passphrase, &salt, &params)?);
+        let secret = cipher.decrypt(Nonce::from_slice(&nonce), ciphertext.as_slice())
+            .map_err(|_| anyhow!("wrong passphrase or corrupted keystore"))?;
+        secret.try_into().map_err(|_| anyhow!("keystore does not contain a 32 byte key"))
+    }
+}
+
+fn derive_key(passphrase: &str, salt: &[u8], params: &scrypt::Params) -> Result<Key> {
+    let mut key = Key::default();
+    scrypt::scrypt(passphrase.as_bytes(), salt, params, &mut key)
+        .map_err(|e| anyhow!("key derivation failed: {}", e))?;
+    Ok(key)
+}
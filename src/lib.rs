@@ -0,0 +1,43 @@
+//! Client library behind the `ocs01-test` cli: wallets, the contract
+//! interface, transaction signing and an async client for the node api.
+//!
+//! ```no_run
+//! # async fn demo() -> anyhow::Result<()> {
+//! use ocs01_test::{Interface, OctraClient, Wallet};
+//!
+//! let wallet = Wallet::load("wallet.json".as_ref())?;
+//! let sk = wallet.signing_key(None)?;
+//! let interface = Interface::load("exec_interface.json".as_ref())?;
+//! let client = OctraClient::new(&wallet.endpoints())?;
+//!
+//! let method = interface.method("factorial", "view")?;
+//! let params = method.encode_params(&["5".to_string()])?;
+//! let result = client.view(&interface.contract, &method.name, &params, &wallet.addr).await?;
+//!
+//! let nonce = client.balance(&wallet.addr).await?.1 + 1;
+//! let tx_hash = client.call(&sk, &interface.contract, "claimToken", &[], nonce).await?;
+//! client.wait(&tx_hash, std::time::Duration::from_secs(100), || {}).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod address;
+pub mod amount;
+pub mod client;
+pub mod error;
+pub mod interface;
+pub mod keystore;
+pub mod nonce;
+pub mod params;
+pub mod rpc;
+pub mod tx;
+pub mod wallet;
+
+pub use address::Address;
+pub use amount::Amount;
+pub use client::OctraClient;
+pub use error::Error;
+pub use interface::{Interface, Method};
+pub use params::{Param, ParamType};
+pub use tx::{ContractCall, Transaction};
+pub use wallet::Wallet;
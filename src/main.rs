@@ -1,294 +1,1311 @@
-use serde::Deserialize;
 use serde_json::json;
-use std::{collections::HashMap, fs, io::{self, Write}, time::{SystemTime, UNIX_EPOCH}};
+use std::{collections::HashMap, fs, io::{self, IsTerminal, Write}, path::{Path, PathBuf}, process::ExitCode, time::Duration};
 use base64::{Engine as _, engine::general_purpose};
-use ed25519_dalek::{Signer, SigningKey as Ed25519SigningKey};
-use reqwest::blocking::Client;
-use anyhow::{Result, bail};
-
-#[derive(Deserialize)]
-struct Wallet {
-    #[serde(rename = "priv")]
-    priv_: String,
-    addr: String,
-    rpc: String,
-}
-
-#[derive(Deserialize)]
-struct Method {
-    name: String,
-    label: String,
-    params: Vec<Param>,
-    #[serde(rename = "type")]
-    method_type: String,
-}
-
-#[derive(Deserialize)]
-struct Param {
-    name: String,
-    #[serde(rename = "type")]
-    #[allow(dead_code)]
-    param_type: String,
-    example: Option<String>,
-    max: Option<u64>,
-}
-
-#[derive(Deserialize)]
-struct Interface {
-    contract: String,
-    methods: Vec<Method>,
-}
-
-#[derive(Deserialize)]
-struct BalanceResponse {
-    balance_raw: String,
-    nonce: u64,
-}
-
-fn api_call<T: for<'de> Deserialize<'de>>(
-    client: &Client,
-    method: &str,
-    url: &str,
-    data: Option<serde_json::Value>
-) -> Result<T> {
-    let response = match method {
-        "GET" => client.get(url).send()?,
-        "POST" => client.post(url).json(&data).send()?,
-        _ => bail!("unsupported method"),
-    };
-    
-    if response.status().as_u16() >= 400 {
-        bail!("api error: {}", response.text()?);
+use ed25519_dalek::SigningKey as Ed25519SigningKey;
+use anyhow::{Context, Result, bail};
+use clap::{ArgGroup, Args, Parser, Subcommand, ValueEnum};
+use futures::{StreamExt, stream};
+use tracing_subscriber::EnvFilter;
+
+use ocs01_test::{Address, Amount, ContractCall, Error, Interface, OctraClient, Param, Wallet, nonce::NonceManager, params, rpc::{self, RetryPolicy}, tx, wallet};
+
+mod batch;
+mod config;
+mod expect;
+mod fuzz;
+mod mock;
+mod profiles;
+
+use batch::{Step, StepKind};
+use config::Config;
+use expect::{Expect, Matcher};
+use profiles::{Profiles, WalletSource};
+
+fn format_result(result_value: &serde_json::Value) -> String {
+    if result_value.is_string() {
+        // If the result is a JSON string (e.g., "hello"), its `to_string()`
+        // representation already includes the quotes (e.g., "\"hello\"").
+        result_value.to_string()
+    } else if result_value.is_boolean() {
+        // If the result is a JSON boolean (e.g., true), its `to_string()`
+        // representation does NOT include quotes (e.g., "true").
+        // We explicitly add quotes here.
+        format!("\"{}\"", result_value)
+    } else if result_value.is_number() {
+        // If the result is a JSON number (e.g., 5), its `to_string()`
+        // representation does NOT include quotes (e.g., "5").
+        // We want it without quotes, so use `to_string()` directly.
+        result_value.to_string()
+    } else {
+        // For null or other types, just convert to string.
+        result_value.to_string()
     }
-    
-    Ok(response.json()?)
 }
 
-fn sign_tx(sk: &Ed25519SigningKey, tx: &HashMap<&str, String>) -> String {
-    let blob = format!(
-        r#"{{"from":"{}","to_":"{}","amount":"{}","nonce":{},"ou":"{}","timestamp":{}}}"#,
-        tx["from"], tx["to_"], tx["amount"], tx["nonce"], tx["ou"], tx["timestamp"]
-    );
-    
-    let signature = sk.sign(blob.as_bytes());
-    general_purpose::STANDARD.encode(signature.to_bytes())
+/// Waits for `tx_hash`, printing a dot per pending poll if `progress`.
+async fn wait_tx(client: &OctraClient, tx_hash: &str, timeout: u64, progress: bool) -> Result<bool> {
+    client.wait(tx_hash, Duration::from_secs(timeout), || {
+        if progress {
+            print!(".");
+            let _ = io::stdout().flush();
+        }
+    }).await
 }
 
-fn get_balance(client: &Client, api_url: &str, addr: &str) -> Result<(f64, u64)> {
-    let balance: BalanceResponse = api_call(
-        client,
-        "GET",
-        &format!("{}/balance/{}", api_url, addr),
-        None
-    )?;
-    
-    Ok((balance.balance_raw.parse::<f64>()? / 1_000_000.0, balance.nonce))
-}
-
-fn view_call(
-    client: &Client,
-    api_url: &str,
-    contract: &str,
-    method: &str,
-    params: &[String],
-    caller: &str
-) -> Result<Option<String>> {
-    let response: serde_json::Value = api_call(
-        client,
-        "POST",
-        &format!("{}/contract/call-view", api_url),
-        Some(json!({
-            "contract": contract,
-            "method": method,
-            "params": params,
-            "caller": caller
-        }))
-    )?;
-    
-    Ok(if response["status"] == "success" {
-        let result_value = &response["result"];
-        let formatted_string = if result_value.is_string() {
-            // If the result is a JSON string (e.g., "hello"), its `to_string()`
-            // representation already includes the quotes (e.g., "\"hello\"").
-            result_value.to_string()
-        } else if result_value.is_boolean() {
-            // If the result is a JSON boolean (e.g., true), its `to_string()`
-            // representation does NOT include quotes (e.g., "true").
-            // We explicitly add quotes here.
-            format!("\"{}\"", result_value.to_string())
-        } else if result_value.is_number() {
-            // If the result is a JSON number (e.g., 5), its `to_string()`
-            // representation does NOT include quotes (e.g., "5").
-            // We want it without quotes, so use `to_string()` directly.
-            result_value.to_string()
-        } else {
-            // For null or other types, just convert to string.
-            result_value.to_string()
+fn read_input(prompt: &str) -> Result<String> {
+    print!("{}", prompt);
+    let mut input = String::new();
+    let read = io::stdout().flush().and_then(|_| io::stdin().read_line(&mut input))
+        .map_err(|e| Error::Input(anyhow::anyhow!("cannot read stdin: {}", e)))?;
+    if read == 0 {
+        return Err(Error::Input(anyhow::anyhow!("unexpected end of input")).into());
+    }
+    Ok(input.trim().to_string())
+}
+
+fn parse_params(params: &[Param]) -> Result<Vec<serde_json::Value>> {
+    params.iter().map(|p| {
+        let prompt = p.prompt();
+        loop {
+            match p.parse(&read_input(&prompt)?) {
+                Ok(value) => break Ok(value),
+                Err(e) => println!("invalid {}: {}", p.name, e),
+            }
+        }
+    }).collect()
+}
+
+#[derive(Parser)]
+#[command(name = "ocs01-test", about = "rust cli for testing ocs01 smart contract")]
+struct Cli {
+    /// output format for subcommands
+    #[arg(long, global = true, value_enum, default_value_t = OutputFormat::Text)]
+    output: OutputFormat,
+    /// use a named wallet from profiles.json instead of wallet.json
+    #[arg(long, global = true, env = "OCS01_PROFILE")]
+    profile: Option<String>,
+    /// wallet file [default: ./wallet.json, then the config dir]
+    #[arg(long, global = true, env = "OCS01_WALLET")]
+    wallet: Option<PathBuf>,
+    /// exec interface file [default: ./exec_interface.json, then the config dir]
+    #[arg(long, global = true, env = "OCS01_INTERFACE")]
+    interface: Option<PathBuf>,
+    /// profiles file [default: ./profiles.json, then the config dir]
+    #[arg(long, global = true, env = "OCS01_PROFILES")]
+    profiles: Option<PathBuf>,
+    /// rpc urls in priority order, comma separated; overrides the ones stored in the wallet
+    #[arg(long, global = true, env = "OCS01_RPC", value_delimiter = ',')]
+    rpc: Vec<String>,
+    /// retries for failed reads and view calls; submitted txs are never retried
+    #[arg(long, global = true, env = "OCS01_RETRIES", default_value_t = 3)]
+    retries: u32,
+    /// initial delay between retries in ms, doubled on each attempt
+    #[arg(long, global = true, env = "OCS01_RETRY_DELAY_MS", default_value_t = 500)]
+    retry_delay_ms: u64,
+    /// log requests to stderr; -vv also logs response bodies and signed payloads
+    #[arg(long, short, global = true, action = clap::ArgAction::Count)]
+    verbose: u8,
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum OutputFormat {
+    Text,
+    Json,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// run a view method and print its result
+    View {
+        method: String,
+        params: Vec<String>,
+        #[command(flatten)]
+        expect: ExpectArgs,
+    },
+    /// sign and submit a call method
+    Call {
+        method: String,
+        params: Vec<String>,
+        /// wait for the tx to be confirmed
+        #[arg(long)]
+        wait: bool,
+        /// confirmation timeout in seconds
+        #[arg(long, default_value_t = 100)]
+        timeout: u64,
+        /// sign with this nonce instead of the next free one
+        #[arg(long)]
+        nonce: Option<u64>,
+        #[command(flatten)]
+        payment: PaymentArgs,
+    },
+    /// transfer oct to another address
+    Send {
+        to: String,
+        /// amount in oct, up to 6 decimal places
+        amount: Amount,
+        /// fee in operation units [default: estimated from the amount]
+        #[arg(long)]
+        ou: Option<u64>,
+        /// wait for the tx to be confirmed
+        #[arg(long)]
+        wait: bool,
+        /// confirmation timeout in seconds
+        #[arg(long, default_value_t = 100)]
+        timeout: u64,
+        /// sign with this nonce instead of the next free one
+        #[arg(long)]
+        nonce: Option<u64>,
+    },
+    /// show wallet balance and nonce
+    Balance,
+    /// compare math view methods against local reference implementations
+    Fuzz {
+        /// methods to fuzz [default: all with a reference implementation]
+        methods: Vec<String>,
+        /// random inputs per method
+        #[arg(long, short = 'n', default_value_t = 20)]
+        iterations: usize,
+        /// seed to replay a previous run
+        #[arg(long)]
+        seed: Option<u64>,
+        /// view calls in flight at once
+        #[arg(long, short, default_value_t = 8)]
+        jobs: usize,
+    },
+    /// run the steps of a yaml or json script and report pass/fail
+    Run {
+        file: PathBuf,
+        /// stop at the first failing step
+        #[arg(long)]
+        fail_fast: bool,
+        /// view calls in flight at once within a run of view steps
+        #[arg(long, short, default_value_t = 8)]
+        jobs: usize,
+    },
+    /// serve a local mock of the node endpoints for offline testing
+    Mock {
+        /// address to listen on, port 0 picks a free one
+        #[arg(long, default_value = "127.0.0.1:8080")]
+        listen: String,
+        /// yaml or json file with scripted contract responses
+        #[arg(long)]
+        script: Option<PathBuf>,
+    },
+    /// build, sign and submit calls separately, e.g. to sign offline
+    Tx {
+        #[command(subcommand)]
+        action: TxCommand,
+    },
+    /// create, import or show the wallet file
+    Wallet {
+        #[command(subcommand)]
+        action: WalletCommand,
+    },
+}
+
+/// Assertions on a view result, failing the command with exit code 7.
+#[derive(Args)]
+#[command(group(ArgGroup::new("check").args(["expect", "approx", "regex"]).multiple(true)))]
+struct ExpectArgs {
+    /// result must equal this json value (plain text is compared as a string)
+    #[arg(long)]
+    expect: Option<String>,
+    /// result must be within --tolerance of this number
+    #[arg(long)]
+    approx: Option<f64>,
+    #[arg(long, requires = "approx")]
+    tolerance: Option<f64>,
+    /// result must match this regex
+    #[arg(long)]
+    regex: Option<String>,
+    /// check the value at this json path of the result, e.g. $.items[0]
+    #[arg(long, requires = "check")]
+    path: Option<String>,
+}
+
+impl ExpectArgs {
+    fn to_expect(&self) -> Option<Expect> {
+        let matcher = Matcher {
+            path: self.path.clone(),
+            equals: self.expect.as_ref().map(|e| {
+                serde_json::from_str(e).unwrap_or_else(|_| serde_json::Value::String(e.clone()))
+            }),
+            approx: self.approx,
+            tolerance: self.tolerance,
+            regex: self.regex.clone(),
         };
-        Some(formatted_string)
-    } else {
-        None
-    })
+        (!matcher.is_empty()).then_some(Expect::Matcher(matcher))
+    }
 }
 
-fn call_contract(
-    client: &Client,
-    api_url: &str,
-    sk: &Ed25519SigningKey,
-    from_addr: &str,
-    contract: &str,
-    method: &str,
-    params: &[String]
-) -> Result<String> {
-    let (_, nonce) = get_balance(client, api_url, from_addr)?;
-    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs_f64();
-    
-    let mut tx = HashMap::new();
-    tx.insert("from", from_addr.to_string());
-    tx.insert("to_", contract.to_string());
-    tx.insert("amount", "0".to_string());
-    tx.insert("nonce", (nonce + 1).to_string());
-    tx.insert("ou", "1".to_string());
-    tx.insert("timestamp", timestamp.to_string());
-    
-    let signature = sign_tx(sk, &tx);
-    let public_key = general_purpose::STANDARD.encode(sk.verifying_key().to_bytes());
-    
-    let response: serde_json::Value = api_call(
-        client,
-        "POST",
-        &format!("{}/call-contract", api_url),
-        Some(json!({
-            "contract": contract,
-            "method": method,
-            "params": params,
-            "caller": from_addr,
-            "nonce": nonce + 1,
-            "timestamp": timestamp,
-            "signature": signature,
-            "public_key": public_key
-        }))
-    )?;
-    
-    Ok(response["tx_hash"].as_str().unwrap_or("").to_string())
+/// What a call pays besides its params.
+#[derive(Args)]
+struct PaymentArgs {
+    /// oct to send to the contract along with the call
+    #[arg(long, default_value = "0")]
+    amount: Amount,
+    /// fee in operation units [default: estimated from the amount]
+    #[arg(long)]
+    ou: Option<u64>,
 }
 
-fn wait_tx(client: &Client, api_url: &str, tx_hash: &str, timeout: u64) -> Result<bool> {
-    let start = SystemTime::now();
-    
-    loop {
-        let elapsed = SystemTime::now().duration_since(start)?.as_secs();
-        if elapsed > timeout {
-            return Ok(false);
-        }
-        
-        let tx: serde_json::Value = api_call(
-            client,
-            "GET",
-            &format!("{}/tx/{}", api_url, tx_hash),
-            None
-        )?;
-        
-        if tx["status"] == "confirmed" {
-            return Ok(true);
-        }
-        
-        print!(".");
-        io::stdout().flush()?;
-        std::thread::sleep(std::time::Duration::from_secs(5));
+impl PaymentArgs {
+    fn apply(&self, call: &mut ContractCall) {
+        call.amount = self.amount;
+        call.ou = self.ou.unwrap_or_else(|| tx::estimate_ou(self.amount));
     }
 }
 
-fn read_input(prompt: &str) -> String {
-    print!("{}", prompt);
-    io::stdout().flush().unwrap();
-    let mut input = String::new();
-    io::stdin().read_line(&mut input).unwrap();
-    input.trim().to_string()
+#[derive(Subcommand)]
+enum TxCommand {
+    /// write an unsigned call payload
+    Build {
+        method: String,
+        params: Vec<String>,
+        /// use this nonce instead of asking the node for the next free one
+        #[arg(long)]
+        nonce: Option<u64>,
+        /// caller address [default: the wallet's]
+        #[arg(long)]
+        from: Option<String>,
+        #[command(flatten)]
+        payment: PaymentArgs,
+        /// write to this file instead of stdout
+        #[arg(long, short)]
+        out: Option<PathBuf>,
+    },
+    /// sign a call payload with the wallet key, without touching the network
+    Sign {
+        /// payload file, - for stdin
+        file: PathBuf,
+        /// write to this file instead of stdout
+        #[arg(long, short)]
+        out: Option<PathBuf>,
+    },
+    /// print a call payload, the blob its signature should cover and whether it does
+    Inspect {
+        /// payload file, - for stdin, or the json itself
+        payload: String,
+    },
+    /// submit a signed call payload
+    Send {
+        /// payload file, - for stdin
+        file: PathBuf,
+        /// wait for the tx to be confirmed
+        #[arg(long)]
+        wait: bool,
+        /// confirmation timeout in seconds
+        #[arg(long, default_value_t = 100)]
+        timeout: u64,
+    },
 }
 
-fn parse_params(params: &[Param]) -> Vec<String> {
-    params.iter().map(|p| {
-        let mut prompt = format!("{}", p.name);
-        if let Some(example) = &p.example {
-            prompt.push_str(&format!(" (e.g. {})", example));
+/// How a new key is stored. Encrypted unless asked otherwise.
+#[derive(Args)]
+struct KeyStorageArgs {
+    /// store the key unencrypted, e.g. for a throwaway test wallet
+    #[arg(long)]
+    plaintext: bool,
+    /// encrypt the key (the default, kept for older scripts)
+    #[arg(long, hide = true, conflicts_with = "plaintext")]
+    encrypt: bool,
+}
+
+#[derive(Subcommand)]
+enum WalletCommand {
+    /// generate a new ed25519 key and write wallet.json
+    New {
+        /// overwrite an existing wallet.json
+        #[arg(long)]
+        force: bool,
+        #[command(flatten)]
+        storage: KeyStorageArgs,
+    },
+    /// write wallet.json from an existing private key (hex or base64), read
+    /// from OCS01_PRIVATE_KEY, a hidden prompt or stdin
+    Import {
+        /// overwrite an existing wallet.json
+        #[arg(long)]
+        force: bool,
+        #[command(flatten)]
+        storage: KeyStorageArgs,
+    },
+    /// print the address and public key of wallet.json
+    Show,
+    /// encrypt the plaintext key of an existing wallet.json
+    Migrate,
+    /// list the profiles in profiles.json
+    List,
+}
+
+const PASSPHRASE_ENV: &str = "OCS01_PASSPHRASE";
+const PRIVATE_KEY_ENV: &str = "OCS01_PRIVATE_KEY";
+
+/// Reads a private key to import from `OCS01_PRIVATE_KEY`, a hidden prompt
+/// on the terminal or piped stdin. Never from argv, where other users could
+/// see it in the process list.
+fn read_private_key() -> Result<String> {
+    if let Ok(key) = std::env::var(PRIVATE_KEY_ENV) {
+        return Ok(key);
+    }
+    let key = if io::stdin().is_terminal() {
+        rpassword::prompt_password("private key: ").context("cannot read the private key")
+    } else {
+        io::read_to_string(io::stdin()).context("cannot read the private key from stdin")
+    };
+    let key = key.map_err(Error::Input)?.trim().to_string();
+    if key.is_empty() {
+        return Err(Error::Input(anyhow::anyhow!("no private key given (pipe it to stdin or set {})", PRIVATE_KEY_ENV)).into());
+    }
+    Ok(key)
+}
+
+/// Reads the keystore passphrase from `OCS01_PASSPHRASE`, falling back to a
+/// hidden prompt on the terminal.
+fn read_passphrase(confirm: bool) -> Result<String> {
+    if let Ok(passphrase) = std::env::var(PASSPHRASE_ENV) {
+        return Ok(passphrase);
+    }
+    let prompt = || -> Result<String> {
+        let passphrase = rpassword::prompt_password("passphrase: ")
+            .with_context(|| format!("cannot read passphrase (set {} for non-interactive use)", PASSPHRASE_ENV))?;
+        if confirm {
+            if passphrase.is_empty() {
+                bail!("passphrase must not be empty");
+            }
+            if rpassword::prompt_password("repeat passphrase: ")? != passphrase {
+                bail!("passphrases do not match");
+            }
         }
-        if let Some(max) = p.max {
-            prompt.push_str(&format!(" (max: {})", max));
+        Ok(passphrase)
+    };
+    prompt().map_err(|e| Error::Input(e).into())
+}
+
+/// Decodes the wallet key, asking for the passphrase if it is encrypted.
+fn unlock(wallet: &Wallet, source: &WalletSource) -> Result<Ed25519SigningKey> {
+    if !wallet.is_encrypted() {
+        eprintln!("warning: {} stores its private key in plaintext, run `wallet migrate` to encrypt it", source);
+        return Ok(wallet.signing_key(None).map_err(Error::Wallet)?);
+    }
+    Ok(wallet.signing_key(Some(&read_passphrase(false)?)).map_err(Error::Wallet)?)
+}
+
+/// The wallet the menu currently acts for.
+struct Session {
+    source: WalletSource,
+    wallet: Wallet,
+    sk: Ed25519SigningKey,
+}
+
+impl Session {
+    fn open(source: WalletSource, config: &Config) -> Result<Self> {
+        let mut wallet = source.load().map_err(Error::Wallet)?;
+        let sk = unlock(&wallet, &source)?;
+        if let Some(rpc) = &config.rpc {
+            wallet.set_endpoints(rpc);
         }
-        prompt.push_str(": ");
-        read_input(&prompt)
-    }).collect()
+        Ok(Session { source, wallet, sk })
+    }
 }
 
-fn main() -> Result<()> {
-    let wallet: Wallet = serde_json::from_str(&fs::read_to_string("wallet.json")?)?;
-    let interface: Interface = serde_json::from_str(&fs::read_to_string("exec_interface.json")?)?;
-    
-    let sk_bytes = general_purpose::STANDARD.decode(&wallet.priv_)?;
-    let sk = Ed25519SigningKey::from_bytes(&sk_bytes.try_into().unwrap());
+/// Asks which profile to use and opens it. Keys that were already unlocked
+/// this session are reused so encrypted profiles only prompt once.
+fn switch_profile(
+    config: &Config,
+    profiles: &Profiles,
+    unlocked: &mut HashMap<String, Ed25519SigningKey>,
+) -> Result<Option<Session>> {
+    let names: Vec<&String> = profiles.profiles.keys().collect();
+    println!();
+    for (i, name) in names.iter().enumerate() {
+        println!("{}. {} ({})", i + 1, name, profiles.profiles[*name].addr);
+    }
+    let choice = read_input("\nprofile: ")?;
+    let name = match choice.parse::<usize>() {
+        Ok(idx) if idx > 0 && idx <= names.len() => names[idx - 1].clone(),
+        _ if profiles.profiles.contains_key(&choice) => choice,
+        _ => return Ok(None),
+    };
     
-    let client = Client::builder()
-        .timeout(std::time::Duration::from_secs(100))
-        .build()?;
+    let source = WalletSource::Profile { path: config.profiles.clone(), name: name.clone() };
+    let mut wallet = profiles.get(&name).map_err(Error::Wallet)?.clone();
+    let sk = match unlocked.get(&name) {
+        Some(sk) => sk.clone(),
+        None => unlock(&wallet, &source)?,
+    };
+    unlocked.insert(name, sk.clone());
+    if let Some(rpc) = &config.rpc {
+        wallet.set_endpoints(rpc);
+    }
+    Ok(Some(Session { source, wallet, sk }))
+}
+
+async fn run_menu(client: &OctraClient, config: &Config, interface: &Interface, mut session: Session) -> Result<()> {
+    let mut unlocked = HashMap::new();
+    let mut nonces = NonceManager::default();
+    if let Some(name) = session.source.profile_name() {
+        unlocked.insert(name.to_string(), session.sk.clone());
+    }
     
     loop {
+        let wallet = &session.wallet;
+        let profiles = Profiles::load(&config.profiles).map_err(Error::Wallet)?;
+        
         println!("\n--- ocs01 test client ---");
         println!("contract: {}", interface.contract);
+        if let Some(name) = session.source.profile_name() {
+            println!("profile: {} ({})", name, wallet.addr);
+        }
         
-        let (balance, nonce) = get_balance(&client, &wallet.rpc, &wallet.addr)?;
-        println!("your balance: {:.6} oct (nonce: {})", balance, nonce);
+        match client.balance(&wallet.addr).await {
+            Ok((balance, nonce)) => {
+                nonces.reconcile(&wallet.addr, nonce);
+                let pending = nonces.pending(&wallet.addr);
+                if pending.is_empty() {
+                    println!("your balance: {} oct (nonce: {})", balance, nonce);
+                } else {
+                    let pending: Vec<String> = pending.iter().map(u64::to_string).collect();
+                    println!("your balance: {} oct (nonce: {}, pending: {})", balance, nonce, pending.join(", "));
+                }
+            }
+            // keep the menu usable so a profile with a dead rpc can be switched away from
+            Err(e) if !profiles.profiles.is_empty() => println!("your balance: unavailable ({:#})", e),
+            Err(e) => return Err(e),
+        }
         println!("\nselect method:");
         
         for (i, method) in interface.methods.iter().enumerate() {
             println!("{}. {}", i + 1, method.label);
         }
+        println!("s. send oct");
+        if !profiles.profiles.is_empty() {
+            println!("p. switch profile");
+        }
         println!("0. exit");
         
-        let choice = read_input("\nchoice: ");
+        let choice = read_input("\nchoice: ")?;
         if choice == "0" {
             break;
         }
         
-        if let Ok(idx) = choice.parse::<usize>() {
-            if idx > 0 && idx <= interface.methods.len() {
-                let method = &interface.methods[idx - 1];
-                println!("\n--- {} ---", method.name);
-                
-                let params = parse_params(&method.params);
-                
-                match method.method_type.as_str() {
-                    "view" => {
-                        match view_call(&client, &wallet.rpc, &interface.contract, &method.name, &params, &wallet.addr) {
-                            Ok(result) => println!("\nresult: {}", result.unwrap_or_else(|| "none".to_string())),
-                            Err(e) => println!("error: {}", e),
-                        }
+        if choice == "s" {
+            menu_send(client, &mut nonces, wallet, &session.sk).await?;
+        } else if choice == "p" && !profiles.profiles.is_empty() {
+            match switch_profile(config, &profiles, &mut unlocked) {
+                Ok(Some(next)) => {
+                    session = next;
+                    client.use_endpoints(&session.wallet.endpoints());
+                    continue;
+                }
+                Ok(None) => println!("unknown profile"),
+                Err(e) => println!("error: {:#}", e),
+            }
+        } else if let Ok(idx) = choice.parse::<usize>()
+            && idx > 0 && idx <= interface.methods.len() {
+            let sk = &session.sk;
+            let method = &interface.methods[idx - 1];
+            println!("\n--- {} ---", method.name);
+            
+            let params = parse_params(&method.params)?;
+            
+            match method.method_type.as_str() {
+                "view" => {
+                    match client.view(&interface.contract, &method.name, &params, &wallet.addr).await {
+                        Ok(result) => println!("\nresult: {}", result.as_ref().map(format_result).unwrap_or_else(|| "none".to_string())),
+                        Err(e) => println!("error: {}", e),
                     }
-                    "call" => {
-                        match call_contract(&client, &wallet.rpc, &sk, &wallet.addr, &interface.contract, &method.name, &params) {
-                            Ok(tx_hash) => {
-                                println!("\ntx: {}", tx_hash);
-                                if read_input("wait for confirmation? y/n: ").to_lowercase() == "y" {
-                                    print!("waiting");
-                                    io::stdout().flush()?;
-                                    match wait_tx(&client, &wallet.rpc, &tx_hash, 100) {
-                                        Ok(true) => println!("\nconfirmed"),
-                                        Ok(false) => println!("\ntimeout"),
-                                        Err(e) => println!("\nerror: {}", e),
-                                    }
-                                }
-                            }
-                            Err(e) => println!("error: {}", e),
+                }
+                "call" => {
+                    let submit = match client.next_nonce(&mut nonces, &wallet.addr).await {
+                        Ok(nonce) => client.call(sk, &interface.contract, &method.name, &params, nonce).await
+                            .inspect_err(|e| if !rpc::maybe_accepted(e) { nonces.release(&wallet.addr, nonce) }),
+                        Err(e) => Err(e),
+                    };
+                    match submit {
+                        Ok(tx_hash) => {
+                            println!("\ntx: {}", tx_hash);
+                            offer_wait(client, &tx_hash).await?;
                         }
+                        Err(e) => println!("error: {}", e),
                     }
-                    _ => println!("unknown method type"),
                 }
+                _ => println!("unknown method type"),
             }
         }
         
-        read_input("\npress enter to continue...");
+        read_input("\npress enter to continue...")?;
     }
     
     println!("\nbye");
     Ok(())
 }
+
+fn emit(output: OutputFormat, doc: serde_json::Value, text: &str) {
+    match output {
+        OutputFormat::Text => println!("{}", text),
+        OutputFormat::Json => println!("{}", doc),
+    }
+}
+
+fn run_wallet(output: OutputFormat, config: &Config, source: WalletSource, action: WalletCommand) -> Result<()> {
+    let rpc = config.rpc.as_ref().and_then(|rpc| rpc.first()).map_or(wallet::DEFAULT_RPC, String::as_str);
+    let (wallet, sk, verb) = match action {
+        WalletCommand::New { force, storage } => {
+            // before asking for a passphrase that would only be thrown away
+            source.ensure_writable(force).map_err(Error::Wallet)?;
+            let mut wallet = Wallet::generate(rpc);
+            wallet.set_endpoints(config.rpc.as_deref().unwrap_or_default());
+            let sk = wallet.signing_key(None).map_err(Error::Wallet)?;
+            if !storage.plaintext {
+                wallet.encrypt(&read_passphrase(true)?).map_err(Error::Wallet)?;
+            }
+            source.save(&wallet, force).map_err(Error::Wallet)?;
+            (wallet, sk, "created")
+        }
+        WalletCommand::Import { force, storage } => {
+            source.ensure_writable(force).map_err(Error::Wallet)?;
+            let mut wallet = Wallet::import(&read_private_key()?, rpc).map_err(Error::Input)?;
+            wallet.set_endpoints(config.rpc.as_deref().unwrap_or_default());
+            let sk = wallet.signing_key(None).map_err(Error::Wallet)?;
+            if !storage.plaintext {
+                wallet.encrypt(&read_passphrase(true)?).map_err(Error::Wallet)?;
+            }
+            source.save(&wallet, force).map_err(Error::Wallet)?;
+            (wallet, sk, "imported")
+        }
+        WalletCommand::Show => {
+            let wallet = source.load().map_err(Error::Wallet)?;
+            let sk = unlock(&wallet, &source)?;
+            (wallet, sk, "loaded")
+        }
+        WalletCommand::Migrate => {
+            let mut wallet = source.load().map_err(Error::Wallet)?;
+            if wallet.is_encrypted() {
+                return Err(Error::Wallet(anyhow::anyhow!("{} is already encrypted", source)).into());
+            }
+            let sk = wallet.signing_key(None).map_err(Error::Wallet)?;
+            wallet.encrypt(&read_passphrase(true)?).map_err(Error::Wallet)?;
+            source.save(&wallet, true).map_err(Error::Wallet)?;
+            (wallet, sk, "encrypted")
+        }
+        WalletCommand::List => {
+            let profiles = Profiles::load(&config.profiles).map_err(Error::Wallet)?;
+            let is_default = |name: &String| profiles.default.as_ref() == Some(name);
+            let text: Vec<String> = profiles.profiles.iter().map(|(name, wallet)| {
+                format!("{}{} {} {}", if is_default(name) { "* " } else { "  " }, name, wallet.addr, wallet.endpoints().join(","))
+            }).collect();
+            emit(
+                output,
+                json!({
+                    "status": "ok",
+                    "default": profiles.default,
+                    "profiles": profiles.profiles.iter().map(|(name, wallet)| json!({
+                        "name": name,
+                        "address": wallet.addr,
+                        "rpc": wallet.rpc,
+                        "rpc_fallback": wallet.rpc_fallback,
+                        "encrypted": wallet.is_encrypted()
+                    })).collect::<Vec<_>>()
+                }),
+                &text.join("\n"),
+            );
+            return Ok(());
+        }
+    };
+    
+    let public_key = general_purpose::STANDARD.encode(sk.verifying_key().to_bytes());
+    emit(
+        output,
+        json!({
+            "status": "ok",
+            "source": source.to_string(),
+            "profile": source.profile_name(),
+            "address": wallet.addr,
+            "public_key": public_key,
+            "encrypted": wallet.is_encrypted(),
+            "rpc": wallet.rpc,
+            "rpc_fallback": wallet.rpc_fallback
+        }),
+        &format!("{} {}\naddress: {}\npublic key: {}\nrpc: {}", verb, source, wallet.addr, public_key, wallet.endpoints().join(", ")),
+    );
+    Ok(())
+}
+
+fn check_expect(expect: &Expect, result: Option<&serde_json::Value>) -> Result<()> {
+    let result = result.ok_or_else(|| Error::Assertion(anyhow::anyhow!("view call returned no result")))?;
+    expect.check(result).map_err(|e| Error::Assertion(e).into())
+}
+
+async fn run_view_step(client: &OctraClient, interface: &Interface, wallet: &Wallet, step: &Step) -> Result<serde_json::Value> {
+    let method = interface.method(step.method(), "view")?;
+    let params = method.encode_params(&step.raw_params())?;
+    let result = client.view(&interface.contract, &method.name, &params, &wallet.addr).await?;
+    if let Some(expect) = &step.expect {
+        check_expect(expect, result.as_ref())?;
+    }
+    Ok(json!({"result": result}))
+}
+
+async fn run_call_step(
+    client: &OctraClient,
+    interface: &Interface,
+    wallet: &Wallet,
+    sk: &Ed25519SigningKey,
+    nonces: &mut NonceManager,
+    step: &Step,
+) -> Result<serde_json::Value> {
+    let method = interface.method(step.method(), "call")?;
+    let params = method.encode_params(&step.raw_params())?;
+    let nonce = client.next_nonce(nonces, &wallet.addr).await?;
+    let tx_hash = client.call(sk, &interface.contract, &method.name, &params, nonce).await
+        .inspect_err(|e| if !rpc::maybe_accepted(e) { nonces.release(&wallet.addr, nonce) })?;
+    if step.wait {
+        let timeout = step.timeout.unwrap_or(100);
+        if !wait_tx(client, &tx_hash, timeout, false).await? {
+            bail!("tx {} not confirmed after {}s", tx_hash, timeout);
+        }
+    }
+    Ok(json!({"tx_hash": tx_hash, "nonce": nonce}))
+}
+
+/// Runs the steps in order. Consecutive view steps cannot affect each other,
+/// so each such run is sent up to `jobs` at a time; call steps go one by one.
+#[allow(clippy::too_many_arguments)]
+async fn run_batch(
+    output: OutputFormat,
+    client: &OctraClient,
+    interface: &Interface,
+    wallet: &Wallet,
+    sk: &Ed25519SigningKey,
+    steps: &[Step],
+    fail_fast: bool,
+    jobs: usize,
+) -> Result<ExitCode> {
+    let mut nonces = NonceManager::default();
+    let mut reports = Vec::new();
+    let mut failed = 0;
+    let mut assertions_failed = 0;
+    let mut start = 0;
+    
+    'steps: while start < steps.len() {
+        let results = if matches!(steps[start].kind(), StepKind::View(_)) {
+            let end = steps[start..].iter().position(|step| matches!(step.kind(), StepKind::Call(_)))
+                .map_or(steps.len(), |n| start + n);
+            stream::iter(&steps[start..end])
+                .map(|step| run_view_step(client, interface, wallet, step))
+                .buffered(jobs.max(1))
+                .collect::<Vec<_>>()
+                .await
+        } else {
+            vec![run_call_step(client, interface, wallet, sk, &mut nonces, &steps[start]).await]
+        };
+        
+        for (i, result) in (start..).zip(results) {
+            let step = &steps[i];
+            start = i + 1;
+            let mut report = json!({"step": i + 1, "name": step.label(), "method": step.method()});
+            let line = match result {
+                Ok(detail) => {
+                    let summary = match (&detail["tx_hash"], &detail["result"]) {
+                        (serde_json::Value::String(tx_hash), _) => format!("tx {}", tx_hash),
+                        (_, result) => format_result(result),
+                    };
+                    report["status"] = json!("pass");
+                    report["detail"] = detail;
+                    format!("[pass] {}. {}: {}", i + 1, step.label(), summary)
+                }
+                Err(e) => {
+                    failed += 1;
+                    let typed = e.downcast_ref::<Error>();
+                    if matches!(typed, Some(Error::Assertion(_))) {
+                        assertions_failed += 1;
+                    }
+                    report["status"] = json!("fail");
+                    report["kind"] = json!(typed.map_or("other", Error::kind));
+                    report["rpc_failure"] = json!(rpc::failure(&e).map(rpc::RpcFailure::class));
+                    report["error"] = json!(format!("{:#}", e));
+                    format!("[fail] {}. {}: {:#}", i + 1, step.label(), e)
+                }
+            };
+            if output == OutputFormat::Text {
+                println!("{}", line);
+            }
+            reports.push(report);
+            if failed > 0 && fail_fast {
+                break 'steps;
+            }
+        }
+    }
+    
+    let passed = reports.len() - failed;
+    let skipped = steps.len() - reports.len();
+    let doc = json!({
+        "status": if failed == 0 { "ok" } else { "error" },
+        "passed": passed,
+        "failed": failed,
+        "skipped": skipped,
+        "steps": reports
+    });
+    match output {
+        OutputFormat::Text => println!("\n{} passed, {} failed, {} skipped", passed, failed, skipped),
+        OutputFormat::Json => println!("{}", doc),
+    }
+    // when only assertions failed, exit like a failed `view --expect` does
+    Ok(match failed {
+        0 => ExitCode::SUCCESS,
+        n if n == assertions_failed => ExitCode::from(7),
+        _ => ExitCode::FAILURE,
+    })
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn run_fuzz(
+    output: OutputFormat,
+    client: &OctraClient,
+    interface: &Interface,
+    wallet: &Wallet,
+    methods: &[String],
+    iterations: usize,
+    seed: Option<u64>,
+    jobs: usize,
+) -> Result<ExitCode> {
+    let names: Vec<&str> = if methods.is_empty() {
+        fuzz::METHODS.iter().copied().filter(|m| interface.methods.iter().any(|im| im.name == *m)).collect()
+    } else {
+        methods.iter().map(String::as_str).collect()
+    };
+    let (mut rng, seed) = fuzz::Rng::new(seed);
+    let mut reports = Vec::new();
+    let mut total_diverged = 0;
+    
+    for name in names {
+        let method = interface.method(name, "view")?;
+        let (mut matched, mut skips, mut divergences) = (0, Vec::new(), Vec::new());
+        
+        let mut cases = Vec::new();
+        for _ in 0..iterations {
+            let args = rng.args(name, &method.params)
+                .ok_or_else(|| Error::Input(anyhow::anyhow!("cannot generate inputs for {}", name)))?;
+            let expected = fuzz::reference(name, &args)
+                .ok_or_else(|| Error::Input(anyhow::anyhow!("no reference implementation for {}", name)))?;
+            let raw: Vec<String> = args.iter().map(i64::to_string).collect();
+            let params = method.encode_params(&raw)?;
+            cases.push((raw, expected, params));
+        }
+        
+        let results: Vec<_> = stream::iter(&cases)
+            .map(|(_, _, params)| client.view(&interface.contract, name, params, &wallet.addr))
+            .buffered(jobs.max(1))
+            .collect()
+            .await;
+        for ((raw, expected, _), result) in cases.iter().zip(results) {
+            let error = result.as_ref().err().map(|e| match rpc::failure(e) {
+                Some(rpc::RpcFailure::Rejected(reason)) => (true, reason.clone()),
+                _ => (false, format!("{:#}", e)),
+            });
+            let actual = result.as_ref().ok().and_then(Option::as_ref);
+            let answer = match &error {
+                None => fuzz::Answer::Result(actual),
+                Some((true, reason)) => fuzz::Answer::Rejected(reason),
+                Some((false, e)) => fuzz::Answer::Failed(e),
+            };
+            let input = format!("{}({})", name, raw.join(", "));
+            match fuzz::compare(expected, &answer) {
+                fuzz::Verdict::Match => matched += 1,
+                fuzz::Verdict::Skipped(reason) => skips.push(json!({
+                    "input": input,
+                    "actual": actual,
+                    "error": error.map(|(_, e)| e),
+                    "reason": reason
+                })),
+                fuzz::Verdict::Diverged(reason) => divergences.push(json!({
+                    "input": input,
+                    "expected": expected.to_string(),
+                    "actual": actual,
+                    "error": error.map(|(_, e)| e),
+                    "reason": reason
+                })),
+            }
+        }
+        
+        total_diverged += divergences.len();
+        if output == OutputFormat::Text {
+            println!("{}: {} ok, {} skipped, {} diverged", name, matched, skips.len(), divergences.len());
+            for d in divergences.iter().chain(&skips) {
+                println!("  {}: {}", d["input"].as_str().unwrap_or(""), d["reason"].as_str().unwrap_or(""));
+            }
+        }
+        reports.push(json!({
+            "method": name,
+            "ok": matched,
+            "skipped": skips.len(),
+            "diverged": divergences.len(),
+            "skips": skips,
+            "divergences": divergences
+        }));
+    }
+    
+    match output {
+        OutputFormat::Text => println!("\nseed: {}", seed),
+        OutputFormat::Json => println!("{}", json!({
+            "status": if total_diverged == 0 { "ok" } else { "error" },
+            "seed": seed,
+            "iterations": iterations,
+            "methods": reports
+        })),
+    }
+    Ok(if total_diverged == 0 { ExitCode::SUCCESS } else { ExitCode::FAILURE })
+}
+
+/// Reports a submitted tx, waiting for its confirmation first if asked to.
+/// `summary` is a json object describing the tx.
+async fn report_submitted(
+    output: OutputFormat,
+    client: &OctraClient,
+    mut summary: serde_json::Value,
+    tx_hash: &str,
+    wait: bool,
+    timeout: u64,
+) -> Result<ExitCode> {
+    summary["tx_hash"] = json!(tx_hash);
+    if !wait {
+        summary["status"] = json!("ok");
+        emit(output, summary, &format!("tx: {}", tx_hash));
+        return Ok(ExitCode::SUCCESS);
+    }
+    
+    if output == OutputFormat::Text {
+        print!("tx: {}\nwaiting", tx_hash);
+        io::stdout().flush()?;
+    }
+    let confirmed = wait_tx(client, tx_hash, timeout, output == OutputFormat::Text).await?;
+    let confirmation = if confirmed { "confirmed" } else { "timeout" };
+    summary["status"] = json!(if confirmed { "ok" } else { "error" });
+    summary["confirmation"] = json!(confirmation);
+    emit(output, summary, &format!("\n{}", confirmation));
+    Ok(if confirmed { ExitCode::SUCCESS } else { ExitCode::FAILURE })
+}
+
+/// Fails unless `addr` can pay `amount` plus the fee for `ou`.
+async fn check_funds(client: &OctraClient, addr: &str, amount: Amount, ou: u64) -> Result<()> {
+    let cost = amount.checked_add(tx::fee(ou))
+        .ok_or_else(|| Error::Input(anyhow::anyhow!("{} oct is too large", amount)))?;
+    let (balance, _) = client.balance(addr).await?;
+    if balance < cost {
+        return Err(Error::Input(anyhow::anyhow!(
+            "insufficient balance: {} oct, the transfer needs {} oct including the fee", balance, cost
+        )).into());
+    }
+    Ok(())
+}
+
+/// Asks whether to wait for `tx_hash` and does so.
+async fn offer_wait(client: &OctraClient, tx_hash: &str) -> Result<()> {
+    if read_input("wait for confirmation? y/n: ")?.to_lowercase() == "y" {
+        print!("waiting");
+        io::stdout().flush()?;
+        match wait_tx(client, tx_hash, 100, true).await {
+            Ok(true) => println!("\nconfirmed"),
+            Ok(false) => println!("\ntimeout"),
+            Err(e) => println!("\nerror: {}", e),
+        }
+    }
+    Ok(())
+}
+
+/// Asks for a recipient and an amount and sends a transfer from the menu.
+async fn menu_send(client: &OctraClient, nonces: &mut NonceManager, wallet: &Wallet, sk: &Ed25519SigningKey) -> Result<()> {
+    let to = loop {
+        match Address::parse(&read_input("to: ")?) {
+            Ok(to) => break to.to_string(),
+            Err(e) => println!("invalid address: {}", e),
+        }
+    };
+    let amount = loop {
+        match read_input("amount (oct): ")?.parse::<Amount>() {
+            Ok(amount) => break amount,
+            Err(e) => println!("invalid amount: {}", e),
+        }
+    };
+    let ou = tx::estimate_ou(amount);
+    println!("fee: {} oct", tx::fee(ou));
+    
+    let submit = match check_funds(client, &wallet.addr, amount, ou).await {
+        Ok(()) => match client.next_nonce(nonces, &wallet.addr).await {
+            Ok(nonce) => client.transfer(sk, &to, amount, ou, nonce).await
+                .inspect_err(|e| if !rpc::maybe_accepted(e) { nonces.release(&wallet.addr, nonce) }),
+            Err(e) => Err(e),
+        },
+        Err(e) => Err(e),
+    };
+    match submit {
+        Ok(tx_hash) => {
+            println!("\ntx: {}", tx_hash);
+            offer_wait(client, &tx_hash).await?;
+        }
+        Err(e) => println!("error: {}", e),
+    }
+    Ok(())
+}
+
+/// Reads a contract call payload from a file, or stdin for `-`.
+fn read_call(path: &Path) -> Result<ContractCall> {
+    let data = if path == Path::new("-") {
+        io::read_to_string(io::stdin()).context("cannot read stdin")
+    } else {
+        fs::read_to_string(path).with_context(|| format!("cannot read {}", path.display()))
+    };
+    let call = data.and_then(|data| serde_json::from_str(&data)
+        .with_context(|| format!("cannot parse {}", path.display())));
+    call.map_err(|e| Error::Input(e).into())
+}
+
+/// Writes a payload to `out`, or prints it when there is none.
+fn write_call(output: OutputFormat, call: &ContractCall, out: Option<&Path>) -> Result<()> {
+    let data = serde_json::to_string_pretty(call)?;
+    let Some(out) = out else {
+        println!("{}", data);
+        return Ok(());
+    };
+    fs::write(out, data + "\n")
+        .with_context(|| format!("cannot write {}", out.display()))
+        .map_err(Error::Input)?;
+    emit(output, json!({"status": "ok", "file": out}), &format!("wrote {}", out.display()));
+    Ok(())
+}
+
+/// Builds, signs or submits a call payload. Only `build` and `send` talk to
+/// the node and neither needs the key, so `sign` can run on an offline machine.
+async fn run_tx(output: OutputFormat, config: &Config, source: WalletSource, retry: RetryPolicy, action: TxCommand) -> Result<ExitCode> {
+    // the wallet is only read for its address and endpoints here
+    let client = |wallet: Option<&Wallet>| {
+        let endpoints = match (&config.rpc, wallet) {
+            (Some(rpc), _) => rpc.clone(),
+            (None, Some(wallet)) => wallet.endpoints(),
+            (None, None) => return Err(Error::Input(anyhow::anyhow!("no wallet to take the rpc from, pass --rpc")).into()),
+        };
+        OctraClient::with_policy(&endpoints, Duration::from_secs(100), retry)
+    };
+    match action {
+        TxCommand::Build { method, params, nonce, from, payment, out } => {
+            let interface = Interface::load(&config.interface).map_err(Error::Interface)?;
+            let method = interface.method(&method, "call")?;
+            let params = method.encode_params(&params)?;
+            let wallet = match &from {
+                Some(_) => source.load().ok(),
+                None => Some(source.load().map_err(Error::Wallet)?),
+            };
+            let caller = match (from, &wallet) {
+                (Some(from), _) => Address::parse(&from).map_err(Error::Input)?.to_string(),
+                (None, wallet) => wallet.as_ref().map(|w| w.addr.clone()).unwrap_or_default(),
+            };
+            let nonce = match nonce {
+                Some(nonce) => nonce,
+                None => client(wallet.as_ref())?.next_nonce(&mut NonceManager::default(), &caller).await?,
+            };
+            let mut call = ContractCall::new(&caller, &interface.contract, &method.name, &params, nonce).map_err(Error::Signing)?;
+            payment.apply(&mut call);
+            write_call(output, &call, out.as_deref())?;
+        }
+        TxCommand::Sign { file, out } => {
+            let mut call = read_call(&file)?;
+            let wallet = source.load().map_err(Error::Wallet)?;
+            let sk = unlock(&wallet, &source)?;
+            call.sign(&sk).map_err(Error::Signing)?;
+            write_call(output, &call, out.as_deref())?;
+        }
+        TxCommand::Inspect { payload } => {
+            let call = if payload.trim_start().starts_with('{') {
+                serde_json::from_str(&payload).context("cannot parse the payload").map_err(Error::Input)?
+            } else {
+                read_call(Path::new(&payload))?
+            };
+            return Ok(inspect_call(output, &call));
+        }
+        TxCommand::Send { file, wait, timeout } => {
+            let call = read_call(&file)?;
+            // a bad signature would only come back as an opaque node error
+            call.verify().map_err(Error::Signing)?;
+            let client = client(source.load().ok().as_ref())?;
+            let tx_hash = client.submit(&call).await?;
+            let summary = json!({"method": call.method, "nonce": call.nonce});
+            return report_submitted(output, &client, summary, &tx_hash, wait, timeout).await;
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Reports the fields of `call`, the blob it should be signed over and every
+/// way its signature fails to match.
+fn inspect_call(output: OutputFormat, call: &ContractCall) -> ExitCode {
+    let blob = call.transaction().signing_blob();
+    let mut problems = Vec::new();
+    let mut signer = None;
+    match (&call.signature, &call.public_key) {
+        (None, _) => problems.push("signature is missing".to_string()),
+        (_, None) => problems.push("public_key is missing".to_string()),
+        (Some(signature), Some(public_key)) => match tx::decode_public_key(public_key) {
+            Ok(key) => {
+                let owner = Address::from_public_key(&key).to_string();
+                if owner != call.caller {
+                    problems.push(format!("public_key belongs to {}, not the caller {}", owner, call.caller));
+                }
+                // checked even for a foreign key, to tell a wrong key from a wrong blob
+                if let Err(e) = call.transaction().verify_signature(signature, &key) {
+                    problems.push(e.to_string());
+                }
+                signer = Some(owner);
+            }
+            Err(e) => problems.push(e.to_string()),
+        },
+    }
+    
+    let mut text = vec![
+        format!("contract: {}", call.contract),
+        format!("method: {}", call.method),
+        format!("params: {}", serde_json::Value::from(call.params.clone())),
+        format!("amount: {} oct", call.amount),
+        format!("ou: {}", call.ou),
+        format!("caller: {}", call.caller),
+        format!("nonce: {}", call.nonce),
+        format!("timestamp: {}", serde_json::Value::from(call.timestamp)),
+        format!("public_key: {}", call.public_key.as_deref().unwrap_or("none")),
+        format!("signer: {}", signer.as_deref().unwrap_or("none")),
+        format!("signature: {}", call.signature.as_deref().unwrap_or("none")),
+        format!("blob: {}", blob),
+    ];
+    if problems.is_empty() {
+        text.push("[pass] signature matches".to_string());
+    }
+    text.extend(problems.iter().map(|p| format!("[fail] {}", p)));
+    emit(
+        output,
+        json!({
+            "status": if problems.is_empty() { "ok" } else { "error" },
+            "call": call,
+            "blob": blob,
+            "signer": signer,
+            "problems": problems
+        }),
+        &text.join("\n"),
+    );
+    // a bad payload exits like a signing error would
+    if problems.is_empty() { ExitCode::SUCCESS } else { ExitCode::from(6) }
+}
+
+/// Picks the wallet to use: the `--profile` entry if given, otherwise
+/// wallet.json, falling back to the default profile when there is no
+/// wallet.json.
+fn wallet_source(config: &Config, profile: Option<String>) -> Result<WalletSource> {
+    let profiles_path = config.profiles.clone();
+    if let Some(name) = profile {
+        return Ok(WalletSource::Profile { path: profiles_path, name });
+    }
+    if !config.wallet.exists()
+        && let Some(name) = Profiles::load(&profiles_path).map_err(Error::Wallet)?.default {
+        return Ok(WalletSource::Profile { path: profiles_path, name });
+    }
+    Ok(WalletSource::File(config.wallet.clone()))
+}
+
+async fn run(cli: Cli) -> Result<ExitCode> {
+    let output = cli.output;
+    if cli.command.is_none() && output == OutputFormat::Json {
+        return Err(Error::Input(anyhow::anyhow!("json output needs a subcommand")).into());
+    }
+    
+    let config = Config::new(cli.wallet, cli.interface, cli.profiles, (!cli.rpc.is_empty()).then_some(cli.rpc));
+    let retry = RetryPolicy {
+        retries: cli.retries,
+        base_delay: Duration::from_millis(cli.retry_delay_ms),
+        max_delay: Duration::from_secs(30),
+    };
+    
+    let command = match cli.command {
+        Some(Command::Wallet { action }) => {
+            let source = match (&action, cli.profile) {
+                (WalletCommand::New { .. } | WalletCommand::Import { .. }, None) => WalletSource::File(config.wallet.clone()),
+                (_, profile) => wallet_source(&config, profile)?,
+            };
+            run_wallet(output, &config, source, action)?;
+            return Ok(ExitCode::SUCCESS);
+        }
+        Some(Command::Mock { listen, script }) => {
+            let script = match script {
+                Some(path) => mock::Script::load(&path).map_err(Error::Input)?,
+                None => mock::Script::default(),
+            };
+            // the mock is a plain blocking server
+            tokio::task::block_in_place(|| mock::serve(&listen, mock::MockNode::new(script), |url| {
+                emit(output, json!({"status": "ok", "url": url}), &format!("mock node listening on {}", url));
+            })).map_err(Error::Input)?;
+            return Ok(ExitCode::SUCCESS);
+        }
+        Some(Command::Tx { action }) => {
+            return run_tx(output, &config, wallet_source(&config, cli.profile)?, retry, action).await;
+        }
+        command => command,
+    };
+    
+    let interface = Interface::load(&config.interface).map_err(Error::Interface)?;
+    let session = Session::open(wallet_source(&config, cli.profile)?, &config)?;
+    let client = OctraClient::with_policy(&session.wallet.endpoints(), Duration::from_secs(100), retry)?;
+    
+    let Some(command) = command else {
+        run_menu(&client, &config, &interface, session).await?;
+        return Ok(ExitCode::SUCCESS);
+    };
+    let Session { wallet, sk, .. } = session;
+    
+    match command {
+        Command::View { method, params, expect } => {
+            let method = interface.method(&method, "view")?;
+            let params = method.encode_params(&params)?;
+            let result = client.view(&interface.contract, &method.name, &params, &wallet.addr).await?;
+            let text = format!("result: {}", result.as_ref().map(format_result).unwrap_or_else(|| "none".to_string()));
+            let Some(expect) = expect.to_expect() else {
+                emit(
+                    output,
+                    json!({"status": "ok", "method": method.name, "params": params, "result": result}),
+                    &text,
+                );
+                return Ok(ExitCode::SUCCESS);
+            };
+            
+            // the result is reported either way so a mismatch can be inspected
+            let checked = check_expect(&expect, result.as_ref());
+            emit(
+                output,
+                json!({
+                    "status": if checked.is_ok() { "ok" } else { "error" },
+                    "method": method.name,
+                    "params": params,
+                    "result": result,
+                    "assertion": checked.as_ref().err().map(|e| format!("{:#}", e))
+                }),
+                &match &checked {
+                    Ok(()) => format!("{}\n[pass]", text),
+                    Err(e) => format!("{}\n[fail] {:#}", text, e),
+                },
+            );
+            if let Err(e) = checked {
+                return Ok(ExitCode::from(e.downcast_ref::<Error>().map_or(1, Error::exit_code)));
+            }
+        }
+        Command::Call { method, params, wait, timeout, nonce, payment } => {
+            let method = interface.method(&method, "call")?;
+            let params = method.encode_params(&params)?;
+            let nonce = match nonce {
+                Some(nonce) => nonce,
+                None => client.next_nonce(&mut NonceManager::default(), &wallet.addr).await?,
+            };
+            let mut call = ContractCall::new(&wallet.addr, &interface.contract, &method.name, &params, nonce)
+                .map_err(Error::Signing)?;
+            payment.apply(&mut call);
+            call.sign(&sk).map_err(Error::Signing)?;
+            let tx_hash = client.submit(&call).await?;
+            let summary = json!({"method": method.name, "nonce": nonce});
+            return report_submitted(output, &client, summary, &tx_hash, wait, timeout).await;
+        }
+        Command::Send { to, amount, ou, wait, timeout, nonce } => {
+            let to = Address::parse(&to).map_err(Error::Input)?.to_string();
+            let ou = ou.unwrap_or_else(|| tx::estimate_ou(amount));
+            check_funds(&client, &wallet.addr, amount, ou).await?;
+            let nonce = match nonce {
+                Some(nonce) => nonce,
+                None => client.next_nonce(&mut NonceManager::default(), &wallet.addr).await?,
+            };
+            let tx_hash = client.transfer(&sk, &to, amount, ou, nonce).await?;
+            let summary = json!({"to": to, "amount": amount.to_string(), "ou": ou, "nonce": nonce});
+            return report_submitted(output, &client, summary, &tx_hash, wait, timeout).await;
+        }
+        Command::Fuzz { methods, iterations, seed, jobs } => {
+            return run_fuzz(output, &client, &interface, &wallet, &methods, iterations, seed, jobs).await;
+        }
+        Command::Run { file, fail_fast, jobs } => {
+            let steps = batch::load(&file).map_err(Error::Input)?;
+            return run_batch(output, &client, &interface, &wallet, &sk, &steps, fail_fast, jobs).await;
+        }
+        Command::Wallet { .. } | Command::Mock { .. } | Command::Tx { .. } => unreachable!("handled before loading the wallet"),
+        Command::Balance => {
+            let (balance, nonce) = client.balance(&wallet.addr).await?;
+            emit(
+                output,
+                json!({"status": "ok", "address": wallet.addr, "balance": balance.to_string(), "nonce": nonce}),
+                &format!("balance: {} oct (nonce: {})", balance, nonce),
+            );
+        }
+    }
+    
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Sends logs to stderr. `RUST_LOG` takes over when no `-v` is given, so
+/// the output can be narrowed further with the usual filter syntax.
+fn init_tracing(verbose: u8) {
+    let filter = match verbose {
+        0 => EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("warn")),
+        1 => EnvFilter::new("warn,ocs01_test=debug"),
+        _ => EnvFilter::new("warn,ocs01_test=trace"),
+    };
+    tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(io::stderr)
+        .with_target(false)
+        .init();
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = match Cli::try_parse() {
+        Ok(cli) => cli,
+        // help and version are errors to clap too, they keep going to stdout
+        Err(e) if e.use_stderr() && json_requested() => {
+            // the message without the usage and --help lines clap appends
+            let rendered = e.to_string();
+            let message = rendered.lines().take_while(|line| !line.is_empty())
+                .map(str::trim).collect::<Vec<_>>().join(" ");
+            let message = message.trim_start_matches("error: ");
+            return report_error(OutputFormat::Json, Error::Input(anyhow::anyhow!("{}", message)).into());
+        }
+        Err(e) => e.exit(),
+    };
+    init_tracing(cli.verbose);
+    let output = cli.output;
+    
+    match run(cli).await {
+        Ok(code) => code,
+        Err(e) => report_error(output, e),
+    }
+}
+
+/// Whether argv asks for `--output json`, for errors that happen before the
+/// arguments could be parsed.
+fn json_requested() -> bool {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    args.iter().any(|arg| arg == "--output=json")
+        || args.windows(2).any(|pair| pair[0] == "--output" && pair[1] == "json")
+}
+
+fn report_error(output: OutputFormat, e: anyhow::Error) -> ExitCode {
+    let typed = e.downcast_ref::<Error>();
+    match output {
+        OutputFormat::Text => {
+            match typed {
+                Some(err) => eprintln!("{} error: {:#}\nhint: {}", err.kind(), e, err.hint()),
+                None => eprintln!("error: {:#}", e),
+            }
+        }
+        OutputFormat::Json => println!("{}", json!({
+            "status": "error",
+            "kind": typed.map_or("other", Error::kind),
+            "rpc_failure": rpc::failure(&e).map(rpc::RpcFailure::class),
+            "error": format!("{:#}", e),
+            "hint": typed.map(Error::hint)
+        })),
+    }
+    ExitCode::from(typed.map_or(1, Error::exit_code))
+}
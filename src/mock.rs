@@ -0,0 +1,286 @@
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::{collections::HashMap, fs, path::Path, str::FromStr};
+use sha2::{Digest, Sha256};
+use tiny_http::{Header, Response, Server};
+use anyhow::{Context, Result, anyhow, bail};
+
+use ocs01_test::{Address, Amount, Transaction, tx::fee};
+
+/// Behaviour of the mock node, loaded from a yaml or json file.
+///
+/// ```yaml
+/// balance: 1000000000   # micro oct every new account starts with
+/// confirm_after: 1      # /tx polls before a tx is confirmed
+/// methods:
+///   factorial:
+///     - params: [5]
+///       result: 120
+///     - error: "n too large"   # no params: matches anything
+///   greetCaller:
+///     - result: "hello"
+/// ```
+///
+/// Cases are tried in order; the first whose `params` match (or that has
+/// none) answers. Call methods without cases are accepted as-is.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Script {
+    #[serde(default = "default_balance")]
+    pub balance: u64,
+    #[serde(default)]
+    pub confirm_after: u32,
+    #[serde(default)]
+    pub methods: HashMap<String, Vec<Case>>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Case {
+    pub params: Option<Vec<Value>>,
+    pub result: Option<Value>,
+    pub error: Option<String>,
+}
+
+fn default_balance() -> u64 {
+    1_000_000_000
+}
+
+impl Default for Script {
+    fn default() -> Self {
+        Script { balance: default_balance(), confirm_after: 0, methods: HashMap::new() }
+    }
+}
+
+impl Script {
+    pub fn load(path: &Path) -> Result<Self> {
+        let data = fs::read_to_string(path)
+            .with_context(|| format!("cannot read {}", path.display()))?;
+        if path.extension().is_some_and(|ext| ext == "json") {
+            serde_json::from_str(&data).with_context(|| format!("cannot parse {}", path.display()))
+        } else {
+            serde_yaml::from_str(&data).with_context(|| format!("cannot parse {}", path.display()))
+        }
+    }
+
+    fn answer(&self, method: &str, params: &[Value]) -> Option<&Case> {
+        self.methods.get(method)?.iter().find(|case| match &case.params {
+            None => true,
+            Some(expected) => expected.len() == params.len()
+                && expected.iter().zip(params).all(|(e, p)| loose(e) == loose(p)),
+        })
+    }
+}
+
+/// Compares script params with request params regardless of whether either
+/// side wrote a number as a string.
+fn loose(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+struct Account {
+    balance: Amount,
+    nonce: u64,
+}
+
+struct Tx {
+    from: String,
+    nonce: u64,
+    polls: u32,
+    confirmed: bool,
+}
+
+/// In-memory node state behind the mock endpoints.
+pub struct MockNode {
+    script: Script,
+    accounts: HashMap<String, Account>,
+    txs: HashMap<String, Tx>,
+}
+
+impl MockNode {
+    pub fn new(script: Script) -> Self {
+        MockNode { script, accounts: HashMap::new(), txs: HashMap::new() }
+    }
+
+    fn account(&mut self, addr: &str) -> &mut Account {
+        let balance = Amount::from_micro(self.script.balance.into());
+        self.accounts.entry(addr.to_string()).or_insert(Account { balance, nonce: 0 })
+    }
+
+    fn pending(&self, addr: &str) -> impl Iterator<Item = &Tx> {
+        self.txs.values().filter(move |tx| tx.from == addr && !tx.confirmed)
+    }
+
+    /// Routes one request and returns the status code and json body.
+    pub fn handle(&mut self, method: &str, url: &str, body: &str) -> (u16, Value) {
+        let result = match (method, url) {
+            ("GET", url) if url.starts_with("/balance/") => Ok(self.balance(&url["/balance/".len()..])),
+            ("GET", url) if url.starts_with("/tx/") => self.tx(&url["/tx/".len()..]),
+            ("GET", "/staging") => Ok(self.staging()),
+            ("POST", "/contract/call-view") => serde_json::from_str(body).map_err(|e| anyhow!("bad json: {}", e))
+                .map(|body| self.call_view(&body)),
+            ("POST", "/call-contract") => serde_json::from_str(body).map_err(|e| anyhow!("bad json: {}", e))
+                .and_then(|body| self.call_contract(&body)),
+            ("POST", "/send-tx") => serde_json::from_str(body).map_err(|e| anyhow!("bad json: {}", e))
+                .and_then(|body| self.send_tx(&body)),
+            _ => return (404, json!({"error": format!("no route for {} {}", method, url)})),
+        };
+        match result {
+            Ok(value) => (200, value),
+            Err(e) => (400, json!({"error": format!("{:#}", e)})),
+        }
+    }
+
+    fn balance(&mut self, addr: &str) -> Value {
+        let account = self.account(addr);
+        json!({"balance_raw": account.balance.micro().to_string(), "nonce": account.nonce})
+    }
+
+    fn staging(&self) -> Value {
+        let staged: Vec<Value> = self.txs.iter()
+            .filter(|(_, tx)| !tx.confirmed)
+            .map(|(hash, tx)| json!({"hash": hash, "from": tx.from, "nonce": tx.nonce}))
+            .collect();
+        json!({"staged_transactions": staged})
+    }
+
+    fn tx(&mut self, hash: &str) -> Result<Value> {
+        let confirm_after = self.script.confirm_after;
+        let Some(tx) = self.txs.get_mut(hash) else {
+            bail!("unknown tx {}", hash);
+        };
+        tx.polls += 1;
+        if !tx.confirmed && tx.polls >= confirm_after {
+            let (from, nonce) = (tx.from.clone(), tx.nonce);
+            // nonces confirm in order, so everything before this one is in too
+            for tx in self.txs.values_mut().filter(|tx| tx.from == from && tx.nonce <= nonce) {
+                tx.confirmed = true;
+            }
+            let account = self.account(&from);
+            account.nonce = account.nonce.max(nonce);
+        }
+        let tx = &self.txs[hash];
+        Ok(json!({"hash": hash, "status": if tx.confirmed { "confirmed" } else { "pending" }}))
+    }
+
+    fn call_view(&self, body: &Value) -> Value {
+        let method = body["method"].as_str().unwrap_or("");
+        let params = body["params"].as_array().cloned().unwrap_or_default();
+        match self.script.answer(method, &params) {
+            Some(Case { error: Some(error), .. }) => json!({"status": "error", "error": error}),
+            Some(case) => json!({"status": "success", "result": case.result.clone().unwrap_or(Value::Null)}),
+            None => json!({"status": "error", "error": format!("no scripted response for {}", method)}),
+        }
+    }
+
+    fn call_contract(&mut self, body: &Value) -> Result<Value> {
+        let field = |name: &str| body.get(name).ok_or_else(|| anyhow!("missing {}", name));
+        let caller = field("caller")?.as_str().ok_or_else(|| anyhow!("caller must be a string"))?;
+        let contract = field("contract")?.as_str().ok_or_else(|| anyhow!("contract must be a string"))?;
+        let method = field("method")?.as_str().ok_or_else(|| anyhow!("method must be a string"))?;
+        let tx = Transaction {
+            from: caller.to_string(),
+            to: contract.to_string(),
+            // left out by clients that only make free calls
+            amount: Amount::from_micro(decimal(body, "amount", 0)?),
+            nonce: field("nonce")?.as_u64().ok_or_else(|| anyhow!("nonce must be an integer"))?,
+            ou: decimal(body, "ou", 1)?,
+            timestamp: field("timestamp")?.as_f64().ok_or_else(|| anyhow!("timestamp must be a number"))?,
+        };
+        self.check(&tx, body)?;
+        let params = body["params"].as_array().cloned().unwrap_or_default();
+        if let Some(Case { error: Some(error), .. }) = self.script.answer(method, &params) {
+            bail!("{}", error);
+        }
+        Ok(json!({"tx_hash": self.apply(&tx), "status": "accepted"}))
+    }
+
+    fn send_tx(&mut self, body: &Value) -> Result<Value> {
+        let field = |name: &str| body.get(name).ok_or_else(|| anyhow!("missing {}", name));
+        let tx = Transaction {
+            from: field("from")?.as_str().ok_or_else(|| anyhow!("from must be a string"))?.to_string(),
+            to: field("to_")?.as_str().ok_or_else(|| anyhow!("to_ must be a string"))?.to_string(),
+            amount: Amount::from_micro(decimal(body, "amount", 0)?),
+            nonce: field("nonce")?.as_u64().ok_or_else(|| anyhow!("nonce must be an integer"))?,
+            ou: decimal(body, "ou", 1)?,
+            timestamp: field("timestamp")?.as_f64().ok_or_else(|| anyhow!("timestamp must be a number"))?,
+        };
+        Address::parse(&tx.to)?;
+        self.check(&tx, body)?;
+        Ok(json!({"tx_hash": self.apply(&tx), "status": "accepted"}))
+    }
+
+    /// Rejects `tx` like a node would: bad signature, wrong nonce or not
+    /// enough balance for the amount and the fee.
+    fn check(&mut self, tx: &Transaction, body: &Value) -> Result<()> {
+        let field = |name: &str| body.get(name).ok_or_else(|| anyhow!("missing {}", name));
+        let signature = field("signature")?.as_str().ok_or_else(|| anyhow!("signature must be a string"))?;
+        let public_key = field("public_key")?.as_str().ok_or_else(|| anyhow!("public_key must be a string"))?;
+        tx.verify(signature, public_key)?;
+
+        let confirmed = self.account(&tx.from).nonce;
+        let expected = self.pending(&tx.from).map(|tx| tx.nonce).fold(confirmed, u64::max) + 1;
+        if tx.nonce != expected {
+            bail!("invalid nonce {} (expected {})", tx.nonce, expected);
+        }
+        let cost = tx.amount.checked_add(fee(tx.ou));
+        if cost.is_none_or(|cost| cost > self.account(&tx.from).balance) {
+            bail!("insufficient balance for {} oct and the fee", tx.amount);
+        }
+        Ok(())
+    }
+
+    /// Moves the amount, charges the fee and records `tx`, returning its hash.
+    fn apply(&mut self, tx: &Transaction) -> String {
+        let hash = hex::encode(Sha256::digest(tx.signing_blob().as_bytes()));
+        let confirmed = self.script.confirm_after == 0;
+        self.txs.insert(hash.clone(), Tx { from: tx.from.clone(), nonce: tx.nonce, polls: 0, confirmed });
+        let cost = tx.amount.checked_add(fee(tx.ou)).expect("checked before applying");
+        let account = self.account(&tx.from);
+        account.balance = account.balance.checked_sub(cost).expect("checked before applying");
+        if confirmed {
+            account.nonce = account.nonce.max(tx.nonce);
+        }
+        let recipient = self.account(&tx.to);
+        // an overflowing credit could only come from a script with absurd balances
+        recipient.balance = recipient.balance.checked_add(tx.amount).unwrap_or(Amount::from_micro(u128::MAX));
+        hash
+    }
+}
+
+/// A decimal string field of `body`, `default` when it is left out.
+fn decimal<T: FromStr>(body: &Value, name: &str, default: T) -> Result<T> {
+    match body.get(name) {
+        None => Ok(default),
+        Some(value) => value.as_str().and_then(|s| s.parse().ok())
+            .ok_or_else(|| anyhow!("{} must be a decimal string", name)),
+    }
+}
+
+/// Serves the mock node on `addr` until the process exits. `on_ready` gets
+/// the base url, which matters when `addr` asks for port 0.
+pub fn serve(addr: &str, node: MockNode, on_ready: impl FnOnce(&str)) -> Result<()> {
+    let server = Server::http(addr).map_err(|e| anyhow!("cannot listen on {}: {}", addr, e))?;
+    on_ready(&format!("http://{}", server.server_addr()));
+    run(server, node);
+    Ok(())
+}
+
+fn run(server: Server, mut node: MockNode) {
+    let json_header = Header::from_bytes("Content-Type", "application/json").expect("static header");
+    for mut request in server.incoming_requests() {
+        let mut body = String::new();
+        let (status, value) = match request.as_reader().read_to_string(&mut body) {
+            Ok(_) => node.handle(request.method().as_str(), request.url(), &body),
+            Err(e) => (400, json!({"error": format!("cannot read body: {}", e)})),
+        };
+        let response = Response::from_string(value.to_string())
+            .with_status_code(status)
+            .with_header(json_header.clone());
+        // a client that hung up is not the mock's problem
+        let _ = request.respond(response);
+    }
+}
@@ -0,0 +1,70 @@
+use std::collections::{BTreeSet, HashMap};
+
+/// Hands out transaction nonces so several transactions from one address can
+/// be in flight at once.
+///
+/// The node only reports the nonce of the last confirmed transaction, so
+/// nonces handed out locally are remembered until the node catches up with
+/// them. The manager does no io itself: callers pass in what the node
+/// reported and get back the nonce to sign with.
+///
+/// ```
+/// use ocs01_test::nonce::NonceManager;
+///
+/// let mut nonces = NonceManager::default();
+/// assert_eq!(nonces.next("oct1", 4, Some(4)), 5);
+/// // 5 is still in flight, the node has not staged it yet
+/// assert_eq!(nonces.next("oct1", 4, None), 6);
+/// // the node staged 5 but dropped 6, so 6 is handed out again
+/// assert_eq!(nonces.next("oct1", 4, Some(5)), 6);
+/// ```
+#[derive(Default)]
+pub struct NonceManager {
+    pending: HashMap<String, BTreeSet<u64>>,
+}
+
+impl NonceManager {
+    /// Reserves the next nonce for `addr`.
+    ///
+    /// `confirmed` is the account nonce from `/balance`. `staged` is the
+    /// highest nonce of this address the node holds, confirmed or waiting in
+    /// its mempool, when the mempool could be read. Local nonces above it
+    /// belong to transactions the node dropped, so they are forgotten rather
+    /// than pushing every later nonce past a gap that never fills.
+    pub fn next(&mut self, addr: &str, confirmed: u64, staged: Option<u64>) -> u64 {
+        self.reconcile(addr, confirmed);
+        let pending = self.pending.entry(addr.to_string()).or_default();
+        if let Some(staged) = staged {
+            pending.retain(|&n| n <= staged);
+        }
+
+        let last = [Some(confirmed), staged, pending.last().copied()]
+            .into_iter()
+            .flatten()
+            .max()
+            .unwrap_or(confirmed);
+        let nonce = last + 1;
+        pending.insert(nonce);
+        nonce
+    }
+
+    /// Forgets pending nonces the node has confirmed.
+    pub fn reconcile(&mut self, addr: &str, confirmed: u64) {
+        if let Some(pending) = self.pending.get_mut(addr) {
+            pending.retain(|&n| n > confirmed);
+        }
+    }
+
+    /// Gives a nonce back after the transaction using it was rejected or
+    /// never sent, so the next call does not leave a gap.
+    pub fn release(&mut self, addr: &str, nonce: u64) {
+        if let Some(pending) = self.pending.get_mut(addr) {
+            pending.remove(&nonce);
+        }
+    }
+
+    /// Nonces handed out for `addr` that the node has not confirmed yet.
+    pub fn pending(&self, addr: &str) -> Vec<u64> {
+        self.pending.get(addr).map(|p| p.iter().copied().collect()).unwrap_or_default()
+    }
+}
@@ -0,0 +1,154 @@
+use serde::Deserialize;
+use serde_json::Value;
+use anyhow::{Result, bail};
+use regex::Regex;
+
+use crate::address::Address;
+
+#[derive(Deserialize)]
+pub struct Param {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub param_type: ParamType,
+    pub example: Option<String>,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    /// Pattern the raw input must match in full.
+    pub regex: Option<String>,
+    /// Raw input values that are accepted, anything else is rejected.
+    #[serde(rename = "enum")]
+    pub allowed: Option<Vec<String>>,
+    #[serde(default)]
+    pub optional: bool,
+    /// Raw input used when the value is left empty.
+    pub default: Option<String>,
+}
+
+impl Param {
+    /// Whether the value can be left out, either because it is optional or
+    /// because the interface provides a default.
+    pub fn can_omit(&self) -> bool {
+        self.optional || self.default.is_some()
+    }
+
+    pub fn prompt(&self) -> String {
+        let mut prompt = self.name.clone();
+        if let Some(example) = &self.example {
+            prompt.push_str(&format!(" (e.g. {})", example));
+        }
+        if let Some(allowed) = &self.allowed {
+            prompt.push_str(&format!(" (one of: {})", allowed.join(", ")));
+        }
+        match (self.min, self.max) {
+            (Some(min), Some(max)) => prompt.push_str(&format!(" ({}..={})", min, max)),
+            (Some(min), None) => prompt.push_str(&format!(" (min: {})", min)),
+            (None, Some(max)) => prompt.push_str(&format!(" (max: {})", max)),
+            (None, None) => {}
+        }
+        if let Some(default) = &self.default {
+            prompt.push_str(&format!(" [default: {}]", default));
+        } else if self.optional {
+            prompt.push_str(" [optional]");
+        }
+        prompt.push_str(": ");
+        prompt
+    }
+
+    /// Applies defaults, checks every constraint from the interface and
+    /// encodes the input. Empty optional values without a default become null.
+    pub fn parse(&self, input: &str) -> Result<Value> {
+        let input = match (input.is_empty(), &self.default) {
+            (true, Some(default)) => default.as_str(),
+            (true, None) if self.optional => return Ok(Value::Null),
+            (true, None) => bail!("a value is required"),
+            (false, _) => input,
+        };
+
+        if let Some(allowed) = &self.allowed
+            && !allowed.iter().any(|a| a == input) {
+            bail!("'{}' must be one of: {}", input, allowed.join(", "));
+        }
+        if let Some(pattern) = &self.regex {
+            let re = Regex::new(&format!("^(?:{})$", pattern))
+                .map_err(|e| anyhow::anyhow!("bad regex in interface: {}", e))?;
+            if !re.is_match(input) {
+                bail!("'{}' does not match {}", input, pattern);
+            }
+        }
+
+        let value = self.param_type.encode(input)?;
+        if let Some(n) = value.as_f64() {
+            if let Some(min) = self.min
+                && n < min {
+                bail!("{} is below the minimum of {}", input, min);
+            }
+            if let Some(max) = self.max
+                && n > max {
+                bail!("{} is above the maximum of {}", input, max);
+            }
+        }
+        Ok(value)
+    }
+}
+
+/// Parameter type as declared in the exec interface. Types this client does
+/// not know yet are kept verbatim and sent as plain strings.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(from = "String")]
+pub enum ParamType {
+    Number,
+    Address,
+    String,
+    Bool,
+    Other(String),
+}
+
+impl From<String> for ParamType {
+    fn from(s: String) -> Self {
+        match s.as_str() {
+            "number" => ParamType::Number,
+            "address" => ParamType::Address,
+            "string" => ParamType::String,
+            "bool" | "boolean" => ParamType::Bool,
+            _ => ParamType::Other(s),
+        }
+    }
+}
+
+impl ParamType {
+    /// Validates raw user input and converts it to the json value the
+    /// contract expects for this type.
+    pub fn encode(&self, input: &str) -> Result<Value> {
+        match self {
+            ParamType::Number => encode_number(input),
+            ParamType::Address => encode_address(input),
+            ParamType::Bool => encode_bool(input),
+            ParamType::String | ParamType::Other(_) => Ok(Value::String(input.to_string())),
+        }
+    }
+}
+
+fn encode_number(input: &str) -> Result<Value> {
+    if let Ok(n) = input.parse::<i64>() {
+        return Ok(n.into());
+    }
+    if let Ok(n) = input.parse::<u64>() {
+        return Ok(n.into());
+    }
+    match input.parse::<f64>() {
+        Ok(n) if n.is_finite() => Ok(n.into()),
+        _ => bail!("'{}' is not a number", input),
+    }
+}
+
+fn encode_address(input: &str) -> Result<Value> {
+    Ok(Value::String(Address::parse(input)?.to_string()))
+}
+
+fn encode_bool(input: &str) -> Result<Value> {
+    match input.to_lowercase().as_str() {
+        "true" | "yes" | "y" | "1" => Ok(Value::Bool(true)),
+        "false" | "no" | "n" | "0" => Ok(Value::Bool(false)),
+        _ => bail!("'{}' is not a bool (expected true/false)", input),
+    }
+}
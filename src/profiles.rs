@@ -0,0 +1,112 @@
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fmt, fs, path::{Path, PathBuf}};
+use anyhow::{Context, Result, bail};
+
+use crate::wallet::{Wallet, write_private};
+
+/// Named wallets kept in one file, each with its own key and rpc endpoint.
+#[derive(Serialize, Deserialize, Default)]
+pub struct Profiles {
+    /// Profile used when neither `--profile` nor a wallet.json is given.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<String>,
+    #[serde(default)]
+    pub profiles: BTreeMap<String, Wallet>,
+}
+
+impl Profiles {
+    /// Loads the profiles file, treating a missing file as empty.
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Profiles::default());
+        }
+        let data = fs::read_to_string(path)
+            .with_context(|| format!("cannot read {}", path.display()))?;
+        serde_json::from_str(&data)
+            .with_context(|| format!("cannot parse {}", path.display()))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        write_private(path, &(serde_json::to_string_pretty(self)? + "\n"))
+    }
+
+    pub fn get(&self, name: &str) -> Result<&Wallet> {
+        match self.profiles.get(name) {
+            Some(wallet) => Ok(wallet),
+            None if self.profiles.is_empty() => bail!("no profile named {} (no profiles defined)", name),
+            None => bail!(
+                "no profile named {} (available: {})",
+                name, self.profiles.keys().cloned().collect::<Vec<_>>().join(", ")
+            ),
+        }
+    }
+
+    /// Adds or replaces a profile. The first profile becomes the default.
+    pub fn insert(&mut self, name: &str, wallet: Wallet, force: bool) -> Result<()> {
+        if self.profiles.contains_key(name) && !force {
+            bail!("profile {} already exists (use --force to overwrite)", name);
+        }
+        self.profiles.insert(name.to_string(), wallet);
+        if self.default.is_none() {
+            self.default = Some(name.to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Where the active wallet is stored: a standalone wallet file or a named
+/// entry in the profiles file.
+pub enum WalletSource {
+    File(PathBuf),
+    Profile { path: PathBuf, name: String },
+}
+
+impl WalletSource {
+    pub fn profile_name(&self) -> Option<&str> {
+        match self {
+            WalletSource::File(_) => None,
+            WalletSource::Profile { name, .. } => Some(name),
+        }
+    }
+
+    pub fn load(&self) -> Result<Wallet> {
+        match self {
+            WalletSource::File(path) => Wallet::load(path),
+            WalletSource::Profile { path, name } => Ok(Profiles::load(path)?.get(name)?.clone()),
+        }
+    }
+
+    /// Fails if saving without `force` would overwrite a wallet.
+    pub fn ensure_writable(&self, force: bool) -> Result<()> {
+        match self {
+            _ if force => Ok(()),
+            WalletSource::File(path) if path.exists() => {
+                bail!("{} already exists (use --force to overwrite)", path.display())
+            }
+            WalletSource::Profile { path, name } if Profiles::load(path)?.profiles.contains_key(name) => {
+                bail!("profile {} already exists (use --force to overwrite)", name)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    pub fn save(&self, wallet: &Wallet, force: bool) -> Result<()> {
+        match self {
+            WalletSource::File(path) => wallet.save(path, force),
+            WalletSource::Profile { path, name } => {
+                let mut profiles = Profiles::load(path)?;
+                profiles.insert(name, wallet.clone(), force)?;
+                profiles.save(path)
+            }
+        }
+    }
+}
+
+impl fmt::Display for WalletSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WalletSource::File(path) => write!(f, "{}", path.display()),
+            WalletSource::Profile { path, name } => write!(f, "profile {} ({})", name, path.display()),
+        }
+    }
+}
@@ -0,0 +1,304 @@
+use serde::Deserialize;
+use std::{sync::{Arc, PoisonError, RwLock, Weak}, time::{Duration, Instant}};
+use rand_core::{OsRng, RngCore};
+use thiserror::Error;
+use tracing::{debug, info, trace};
+use anyhow::{Result, anyhow};
+
+use crate::error::Error;
+
+/// How often and how patiently idempotent requests are retried.
+#[derive(Clone, Copy)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt.
+    pub retries: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl RetryPolicy {
+    /// Exponential backoff with jitter: attempt `n` waits a random time
+    /// between half and all of `base_delay * 2^n`, capped at `max_delay`.
+    fn delay(&self, attempt: u32) -> Duration {
+        let full = self.base_delay.saturating_mul(1 << attempt.min(16)).min(self.max_delay);
+        let jitter = 0.5 + (OsRng.next_u32() as f64 / u32::MAX as f64) / 2.0;
+        full.mul_f64(jitter)
+    }
+}
+
+/// How often the background probe checks endpoints that are in use.
+const PROBE_INTERVAL: Duration = Duration::from_secs(30);
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+struct Endpoint {
+    url: String,
+    healthy: bool,
+}
+
+/// Http client for the node api: a priority list of endpoints, their health
+/// and the retry policy.
+///
+/// Requests go to the first healthy endpoint and fail over to the next one
+/// when it does not answer. Endpoints that failed are marked unhealthy and
+/// only tried after the healthy ones until the probe sees them answer again.
+#[derive(Clone)]
+pub struct Client {
+    http: reqwest::Client,
+    retry: RetryPolicy,
+    endpoints: Arc<RwLock<Vec<Endpoint>>>,
+}
+
+impl Client {
+    /// Builds the client. Inside a tokio runtime this also starts the
+    /// background health probe, which ends once the client and all its
+    /// clones are dropped; outside one, an endpoint that failed is only
+    /// tried again once the healthy ones fail too.
+    pub fn new(timeout: Duration, retry: RetryPolicy) -> Result<Self> {
+        let http = reqwest::Client::builder()
+            .timeout(timeout)
+            .build()?;
+        let client = Client { http, retry, endpoints: Arc::default() };
+        if let Ok(runtime) = tokio::runtime::Handle::try_current() {
+            let (http, endpoints) = (client.http.clone(), Arc::downgrade(&client.endpoints));
+            runtime.spawn(probe(http, endpoints));
+        }
+        Ok(client)
+    }
+
+    /// Switches to `urls`, in priority order. All start out healthy.
+    pub fn use_endpoints(&self, urls: &[String]) {
+        let endpoints = urls.iter()
+            .map(|url| Endpoint { url: url.trim_end_matches('/').to_string(), healthy: true })
+            .collect();
+        *self.endpoints.write().unwrap_or_else(PoisonError::into_inner) = endpoints;
+    }
+
+    /// Healthy endpoints first, each group in priority order.
+    fn candidates(&self) -> Vec<String> {
+        let endpoints = self.endpoints.read().unwrap_or_else(PoisonError::into_inner);
+        let (healthy, unhealthy): (Vec<&Endpoint>, Vec<&Endpoint>) = endpoints.iter().partition(|e| e.healthy);
+        healthy.into_iter().chain(unhealthy).map(|e| e.url.clone()).collect()
+    }
+
+    fn mark(&self, url: &str, healthy: bool) {
+        mark(&self.endpoints, url, healthy);
+    }
+}
+
+fn mark(endpoints: &RwLock<Vec<Endpoint>>, url: &str, healthy: bool) {
+    let mut endpoints = endpoints.write().unwrap_or_else(PoisonError::into_inner);
+    if let Some(endpoint) = endpoints.iter_mut().find(|e| e.url == url) {
+        endpoint.healthy = healthy;
+    }
+}
+
+/// Checks every endpoint with a cheap read every [`PROBE_INTERVAL`]. With a
+/// single endpoint there is nothing to fail over to, so it is left alone.
+/// Only a weak reference is held, so the loop stops with the client.
+async fn probe(http: reqwest::Client, endpoints: Weak<RwLock<Vec<Endpoint>>>) {
+    loop {
+        tokio::time::sleep(PROBE_INTERVAL).await;
+        let Some(endpoints) = endpoints.upgrade() else {
+            return;
+        };
+        let urls: Vec<String> = endpoints.read().unwrap_or_else(PoisonError::into_inner)
+            .iter().map(|e| e.url.clone()).collect();
+        if urls.len() < 2 {
+            continue;
+        }
+        for url in urls {
+            let healthy = http.get(format!("{}/staging", url))
+                .timeout(PROBE_TIMEOUT)
+                .send()
+                .await
+                .is_ok_and(|response| !response.status().is_server_error());
+            debug!(endpoint = %url, healthy, "probe");
+            mark(&endpoints, &url, healthy);
+        }
+    }
+}
+
+/// Why a single request to the node failed.
+#[derive(Debug, Error)]
+pub enum RpcFailure {
+    #[error("transport error: {0}")]
+    Transport(reqwest::Error),
+    #[error("client error {status}: {body}")]
+    Client { status: u16, body: String },
+    #[error("server error {status}: {body}")]
+    Server { status: u16, body: String },
+    #[error("decode error: {0}")]
+    Decode(String),
+    /// The node answered but refused the request, e.g. a view call the
+    /// contract failed.
+    #[error("rejected by the node: {0}")]
+    Rejected(String),
+}
+
+impl RpcFailure {
+    pub fn class(&self) -> &'static str {
+        match self {
+            RpcFailure::Transport(_) => "transport",
+            RpcFailure::Client { .. } => "client",
+            RpcFailure::Server { .. } => "server",
+            RpcFailure::Decode(_) => "decode",
+            RpcFailure::Rejected(_) => "rejected",
+        }
+    }
+
+    /// Transport errors and 5xx responses are worth another attempt; a 4xx
+    /// or an unparseable body will not get better by asking again.
+    pub fn is_transient(&self) -> bool {
+        matches!(self, RpcFailure::Transport(_) | RpcFailure::Server { .. })
+    }
+
+    /// The request never reached the node, so sending it elsewhere cannot
+    /// apply it twice.
+    fn not_sent(&self) -> bool {
+        matches!(self, RpcFailure::Transport(e) if e.is_connect())
+    }
+
+    /// Whether a transaction submitted in the failed request may still have
+    /// been taken by the node: anything but a refusal or a request that
+    /// never went out, e.g. a timeout or a 5xx after the node read it.
+    pub fn maybe_accepted(&self) -> bool {
+        !matches!(self, RpcFailure::Client { .. } | RpcFailure::Rejected(_)) && !self.not_sent()
+    }
+}
+
+/// Sends a request for `path` (e.g. `/balance/oct...`) to the node and
+/// decodes the json response.
+///
+/// A transient failure moves on to the next endpoint. Requests marked
+/// `idempotent` (reads and view calls) are then retried on all endpoints
+/// according to the client's policy. Anything else, in particular
+/// submitting a transaction, only fails over when the connection could not
+/// be made and is never retried: a timeout there does not mean the node did
+/// not accept it.
+pub async fn api_call<T: for<'de> Deserialize<'de>>(
+    client: &Client,
+    method: &str,
+    path: &str,
+    data: Option<serde_json::Value>,
+    idempotent: bool,
+) -> Result<T> {
+    if method != "GET" && method != "POST" {
+        return Err(Error::Rpc(anyhow!("unsupported method {}", method)).into());
+    }
+    let retries = if idempotent { client.retry.retries } else { 0 };
+    let mut attempt = 0;
+    loop {
+        let candidates = client.candidates();
+        if candidates.is_empty() {
+            return Err(Error::Rpc(anyhow!("no rpc endpoint configured")).into());
+        }
+        let mut tried = 0;
+        let mut last = None;
+        for (i, base) in candidates.iter().enumerate() {
+            tried += 1;
+            let failure = match send(client, method, base, path, &data).await {
+                Ok(value) => {
+                    client.mark(base, true);
+                    return Ok(value);
+                }
+                Err(failure) => failure,
+            };
+            if failure.is_transient() {
+                client.mark(base, false);
+            }
+            let failover = failure.is_transient() && (idempotent || failure.not_sent());
+            if failover && let Some(next) = candidates.get(i + 1) {
+                info!(method, path, endpoint = %base, next = %next, error = %failure, "failing over");
+            }
+            last = Some(failure);
+            if !failover {
+                break;
+            }
+        }
+        let failure = last.expect("at least one endpoint was tried");
+        if failure.is_transient() && attempt < retries {
+            let delay = client.retry.delay(attempt);
+            info!(method, path, attempt = attempt + 1, delay_ms = delay.as_millis() as u64, "retrying");
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+            continue;
+        }
+        let e = anyhow::Error::from(failure);
+        let e = match (attempt, tried) {
+            (0, 1) => e,
+            (0, n) => e.context(format!("all {} endpoints failed", n)),
+            (_, _) => e.context(format!("gave up after {} attempts", attempt + 1)),
+        };
+        return Err(Error::Rpc(e).into());
+    }
+}
+
+async fn send<T: for<'de> Deserialize<'de>>(
+    client: &Client,
+    method: &str,
+    base: &str,
+    path: &str,
+    data: &Option<serde_json::Value>,
+) -> std::result::Result<T, RpcFailure> {
+    let url = format!("{}{}", base, path);
+    let request = match data {
+        Some(data) if method == "POST" => {
+            debug!(method, url, body = %redact(data), "request");
+            client.http.post(&url).json(data)
+        }
+        _ => {
+            debug!(method, url, "request");
+            client.http.get(&url)
+        }
+    };
+    let start = Instant::now();
+    let response = request.send().await.map_err(|e| {
+        debug!(method, url, endpoint = base, latency_ms = start.elapsed().as_millis() as u64, error = %e, "no response");
+        RpcFailure::Transport(e)
+    })?;
+
+    let status = response.status().as_u16();
+    let body = response.text().await.map_err(RpcFailure::Transport)?;
+    debug!(method, url, endpoint = base, status, latency_ms = start.elapsed().as_millis() as u64, "response");
+    trace!(body, "response body");
+    match status {
+        500.. => return Err(RpcFailure::Server { status, body }),
+        400.. => return Err(RpcFailure::Client { status, body }),
+        _ => {}
+    }
+    serde_json::from_str(&body).map_err(|e| RpcFailure::Decode(format!("{} in {:?}", e, body)))
+}
+
+/// Fields that must never reach a log, at any depth.
+const SECRET_FIELDS: &[&str] = &["priv", "private_key", "passphrase", "keystore"];
+
+/// Copy of a request body that is safe to log.
+fn redact(value: &serde_json::Value) -> serde_json::Value {
+    match value {
+        serde_json::Value::Object(map) => map.iter().map(|(key, value)| {
+            let value = if SECRET_FIELDS.contains(&key.as_str()) {
+                serde_json::Value::from("<redacted>")
+            } else {
+                redact(value)
+            };
+            (key.clone(), value)
+        }).collect(),
+        serde_json::Value::Array(items) => items.iter().map(redact).collect(),
+        other => other.clone(),
+    }
+}
+
+/// Whether the transaction whose submission failed with `e` may have reached
+/// the node anyway. Errors that are no request failure, like a signing
+/// error, happened before anything was sent.
+pub fn maybe_accepted(e: &anyhow::Error) -> bool {
+    failure(e).is_some_and(RpcFailure::maybe_accepted)
+}
+
+/// The request failure behind `e`, if it is one.
+pub fn failure(e: &anyhow::Error) -> Option<&RpcFailure> {
+    match e.downcast_ref::<Error>()? {
+        Error::Rpc(inner) => inner.downcast_ref(),
+        _ => None,
+    }
+}
@@ -0,0 +1,159 @@
+use serde::{Deserialize, Serialize};
+use std::{fs, io::Write, path::Path};
+use base64::{Engine as _, engine::general_purpose};
+use ed25519_dalek::SigningKey as Ed25519SigningKey;
+use rand_core::OsRng;
+use anyhow::{Context, Result, bail};
+
+use crate::{address::Address, keystore::Keystore};
+
+pub const DEFAULT_RPC: &str = "https://octra.network";
+
+/// Writes a file that holds key material so only the owner can read it
+/// (mode 0600 on unix). The data goes to a temp file next to `path` that is
+/// synced and then renamed over it, so an interrupted write never leaves a
+/// truncated key file behind.
+pub fn write_private(path: &Path, data: &str) -> Result<()> {
+    let name = path.file_name().with_context(|| format!("{} is not a file path", path.display()))?;
+    let tmp = path.with_file_name(format!(".{}.{}.tmp", name.to_string_lossy(), std::process::id()));
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+    let written = options.open(&tmp)
+        .and_then(|mut file| {
+            file.write_all(data.as_bytes())?;
+            file.sync_all()
+        })
+        .and_then(|()| fs::rename(&tmp, path));
+    if let Err(e) = written {
+        let _ = fs::remove_file(&tmp);
+        return Err(e).with_context(|| format!("cannot write {}", path.display()));
+    }
+    // make the rename itself durable; not every platform can sync a directory
+    #[cfg(unix)]
+    if let Some(dir) = path.parent().map(|dir| if dir.as_os_str().is_empty() { Path::new(".") } else { dir }) {
+        let _ = fs::File::open(dir).and_then(|dir| dir.sync_all());
+    }
+    Ok(())
+}
+
+/// Contents of `wallet.json`. The private key is either stored in plaintext
+/// base64 under `priv` or encrypted under `keystore`.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Wallet {
+    #[serde(rename = "priv", default, skip_serializing_if = "Option::is_none")]
+    pub priv_: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keystore: Option<Keystore>,
+    pub addr: String,
+    pub rpc: String,
+    /// Endpoints tried in order when `rpc` fails.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rpc_fallback: Vec<String>,
+}
+
+impl Wallet {
+    pub fn generate(rpc: &str) -> Self {
+        Wallet::from_signing_key(&Ed25519SigningKey::generate(&mut OsRng), rpc)
+    }
+
+    pub fn from_signing_key(sk: &Ed25519SigningKey, rpc: &str) -> Self {
+        Wallet {
+            priv_: Some(general_purpose::STANDARD.encode(sk.to_bytes())),
+            keystore: None,
+            addr: Address::from_public_key(&sk.verifying_key()).to_string(),
+            rpc: rpc.to_string(),
+            rpc_fallback: Vec::new(),
+        }
+    }
+
+    /// Rpc endpoints in priority order.
+    pub fn endpoints(&self) -> Vec<String> {
+        std::iter::once(&self.rpc).chain(&self.rpc_fallback).cloned().collect()
+    }
+
+    /// Replaces the endpoints; the first one becomes `rpc`.
+    pub fn set_endpoints(&mut self, endpoints: &[String]) {
+        if let Some((first, rest)) = endpoints.split_first() {
+            self.rpc = first.clone();
+            self.rpc_fallback = rest.to_vec();
+        }
+    }
+
+    /// Builds a wallet from a raw private key given as hex or base64. Both the
+    /// 32 byte seed and the 64 byte seed+public key form are accepted.
+    pub fn import(key: &str, rpc: &str) -> Result<Self> {
+        let key = key.trim();
+        let bytes = match hex::decode(key) {
+            Ok(bytes) => bytes,
+            Err(_) => general_purpose::STANDARD.decode(key)
+                .context("private key is neither hex nor base64")?,
+        };
+        Ok(Wallet::from_signing_key(&signing_key_from_bytes(&bytes)?, rpc))
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let data = fs::read_to_string(path)
+            .with_context(|| format!("cannot read {}", path.display()))?;
+        serde_json::from_str(&data)
+            .with_context(|| format!("cannot parse {}", path.display()))
+    }
+
+    pub fn save(&self, path: &Path, force: bool) -> Result<()> {
+        if path.exists() && !force {
+            bail!("{} already exists (use --force to overwrite)", path.display());
+        }
+        write_private(path, &(serde_json::to_string_pretty(self)? + "\n"))
+    }
+
+    pub fn is_encrypted(&self) -> bool {
+        self.keystore.is_some()
+    }
+
+    /// Replaces the plaintext key with a keystore sealed by `passphrase`.
+    pub fn encrypt(&mut self, passphrase: &str) -> Result<()> {
+        let sk = self.signing_key(None)?;
+        self.keystore = Some(Keystore::encrypt(&sk.to_bytes(), passphrase)?);
+        self.priv_ = None;
+        Ok(())
+    }
+
+    /// Decodes the private key, decrypting the keystore with `passphrase` if
+    /// the wallet is encrypted, and checks that `addr` belongs to it.
+    pub fn signing_key(&self, passphrase: Option<&str>) -> Result<Ed25519SigningKey> {
+        let sk = match (&self.keystore, &self.priv_) {
+            (Some(keystore), _) => match passphrase {
+                Some(passphrase) => Ed25519SigningKey::from_bytes(&keystore.decrypt(passphrase)?),
+                None => bail!("wallet is encrypted and no passphrase was given"),
+            },
+            (None, Some(priv_)) => {
+                let bytes = general_purpose::STANDARD.decode(priv_)
+                    .context("wallet priv is not valid base64")?;
+                signing_key_from_bytes(&bytes)?
+            }
+            (None, None) => bail!("wallet has neither priv nor keystore"),
+        };
+
+        let derived = Address::from_public_key(&sk.verifying_key());
+        if self.addr.parse::<Address>().context("wallet addr")? != derived {
+            bail!("wallet addr {} does not match its private key (expected {})", self.addr, derived);
+        }
+        Ok(sk)
+    }
+}
+
+fn signing_key_from_bytes(bytes: &[u8]) -> Result<Ed25519SigningKey> {
+    match bytes.len() {
+        32 => Ok(Ed25519SigningKey::from_bytes(bytes.try_into()?)),
+        64 => {
+            let sk = Ed25519SigningKey::from_bytes(bytes[..32].try_into()?);
+            if sk.verifying_key().as_bytes() != &bytes[32..] {
+                bail!("64 byte private key does not contain its own public key");
+            }
+            Ok(sk)
+        }
+        n => bail!("private key is {} bytes, expected 32", n),
+    }
+}